@@ -0,0 +1,58 @@
+//! Telling screen readers what happens on the canvas, through an ARIA live region.
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Out of sight, but still read out.
+const HIDDEN_STYLE: &str = "position: absolute; width: 1px; height: 1px; margin: -1px; \
+    overflow: hidden; clip: rect(0 0 0 0); white-space: nowrap; border: 0;";
+
+pub struct Announcer {
+    element: web_sys::HtmlElement,
+    /// Whether the region was made by us, rather than found in the page.
+    created: bool,
+}
+
+impl Announcer {
+    /// Speaks through the element with `id="announcer"`,
+    /// or through a hidden one put after the canvas.
+    pub fn new(document: &web_sys::Document) -> Result<Announcer, JsValue> {
+        let (element, created) = match document.get_element_by_id("announcer") {
+            Some(element) => (element.dyn_into::<web_sys::HtmlElement>()?, false),
+            None => {
+                let element = document
+                    .create_element("div")?
+                    .dyn_into::<web_sys::HtmlElement>()?;
+                element.set_id("announcer");
+                element.set_attribute("style", HIDDEN_STYLE)?;
+                let canvas = document
+                    .get_element_by_id("canvas")
+                    .expect("Need a canvas element with id=\"canvas\".");
+                canvas.after_with_node_1(&element)?;
+                (element, true)
+            }
+        };
+        element.set_attribute("role", "status")?;
+        element.set_attribute("aria-live", "polite")?;
+        element.set_attribute("aria-atomic", "true")?;
+        Ok(Announcer { element, created })
+    }
+    /// Reads `text` out, once whatever is being read is over.
+    pub fn say(&self, text: &str) {
+        //Emptying first, and filling on the next frame, makes the same text be read again.
+        //Screen readers miss changes undone within the same frame.
+        self.element.set_text_content(None);
+        let element = self.element.clone();
+        let text = text.to_string();
+        let fill = Closure::once_into_js(move || element.set_text_content(Some(&text)));
+        let _ = web_sys::window()
+            .unwrap()
+            .request_animation_frame(fill.unchecked_ref());
+    }
+    pub fn remove(&self) {
+        if self.created {
+            self.element.remove();
+        } else {
+            self.element.set_text_content(None);
+        }
+    }
+}
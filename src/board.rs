@@ -0,0 +1,708 @@
+//! Shapes of the play area.
+//!
+//! Every shape can be written as a line of text, as used by the notation and saves:
+//!
+//! ```text
+//! 1280x720                   a rectangle, with +X+Y if it is not at the origin
+//! circle (360,360) r360      a round arena
+//! regular 6 (360,360) r360   a regular polygon, with aDEGREES if it is turned
+//! rounded 1280x720 r80       a rectangle with rounded corners
+//! polygon (0,0) (600,0) (300,500)
+//! ```
+use crate::renderer::Renderer;
+use crate::shape::{Circle, Position2d, Rect};
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+/// The most sides of a regular polygon, and corners of any polygon, a board may have.
+/// Boards come from shared links, so this keeps the checks on them quick.
+const MAX_SIDES: u32 = 100;
+const MAX_POINTS: usize = 200;
+/// How far from the origin a board may reach, and how many times longer than wide it may be.
+const MAX_EXTENT: f64 = 100_000.0;
+const MAX_ASPECT: f64 = 100.0;
+
+/// The play area every circle has to stay inside.
+pub trait Board {
+    /// The smallest rectangle around the play area.
+    fn bounds(&self) -> Rect;
+    /// How far `point` is from the edge: positive inside, negative outside.
+    /// It must not change faster than `point` moves, as the solver relies on it.
+    fn clearance(&self, point: Position2d) -> f64;
+    /// Whether `circle` lies entirely inside. Touching the edge is fine.
+    fn contains(&self, circle: &Circle) -> bool {
+        self.clearance(circle.position()) >= circle.radius()
+    }
+    /// Adds the outline of the play area to the current path of `renderer`.
+    fn trace(&self, renderer: &mut dyn Renderer);
+}
+
+impl Board for Rect {
+    fn bounds(&self) -> Rect {
+        *self
+    }
+    fn clearance(&self, point: Position2d) -> f64 {
+        let origin = self.position();
+        let (left, right) = (point.x - origin.x, origin.x + self.width() - point.x);
+        let (top, bottom) = (point.y - origin.y, origin.y + self.height() - point.y);
+        let inside = left.min(right).min(top).min(bottom);
+        if inside >= 0.0 {
+            inside
+        } else {
+            //The true distance, so that rectangles can be obstacles too.
+            let dx = left.min(right).min(0.0);
+            let dy = top.min(bottom).min(0.0);
+            -dx.hypot(dy)
+        }
+    }
+    fn trace(&self, renderer: &mut dyn Renderer) {
+        let origin = self.position();
+        renderer.rect(origin.x, origin.y, self.width(), self.height());
+    }
+}
+
+/// A round arena.
+impl Board for Circle {
+    fn bounds(&self) -> Rect {
+        let center = self.position();
+        let radius = self.radius();
+        Rect::new(
+            center.x - radius,
+            center.y - radius,
+            radius * 2.0,
+            radius * 2.0,
+        )
+    }
+    fn clearance(&self, point: Position2d) -> f64 {
+        self.radius() - self.position().distance(&point)
+    }
+    fn trace(&self, renderer: &mut dyn Renderer) {
+        let center = self.position();
+        renderer.arc(center.x, center.y, self.radius(), 0.0, PI * 2.0);
+        renderer.close_path();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegularPolygon {
+    pub center: Position2d,
+    /// Distance from the center to the corners.
+    pub radius: f64,
+    pub sides: u32,
+    /// Clockwise turn in degrees. At zero, a corner points straight right.
+    pub rotation: f64,
+}
+
+impl RegularPolygon {
+    fn corner(&self, idx: u32) -> Position2d {
+        let angle = self.rotation.to_radians() + 2.0 * PI * idx as f64 / self.sides as f64;
+        Position2d {
+            x: self.center.x + self.radius * angle.cos(),
+            y: self.center.y + self.radius * angle.sin(),
+        }
+    }
+}
+
+impl Board for RegularPolygon {
+    fn bounds(&self) -> Rect {
+        Polygon::bounds_of((0..self.sides).map(|idx| self.corner(idx)))
+    }
+    fn clearance(&self, point: Position2d) -> f64 {
+        //Inside a convex polygon, the nearest edge is the one whose line is nearest.
+        let apothem = self.radius * (PI / self.sides as f64).cos();
+        let (dx, dy) = (point.x - self.center.x, point.y - self.center.y);
+        let inside = (0..self.sides)
+            .map(|idx| {
+                let angle =
+                    self.rotation.to_radians() + 2.0 * PI * (idx as f64 + 0.5) / self.sides as f64;
+                apothem - (dx * angle.cos() + dy * angle.sin())
+            })
+            .fold(f64::INFINITY, f64::min);
+        if inside >= 0.0 {
+            inside
+        } else {
+            //Outside, the lines of the edges come nearer than the corners, so measure the edges.
+            let distance = (0..self.sides)
+                .map(|idx| segment_distance(point, self.corner(idx), self.corner(idx + 1)))
+                .fold(f64::INFINITY, f64::min);
+            -distance
+        }
+    }
+    fn trace(&self, renderer: &mut dyn Renderer) {
+        let first = self.corner(0);
+        renderer.move_to(first.x, first.y);
+        for idx in 1..self.sides {
+            let corner = self.corner(idx);
+            renderer.line_to(corner.x, corner.y);
+        }
+        renderer.close_path();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundedRect {
+    pub rect: Rect,
+    /// Radius of the corners, at most half the shorter side.
+    pub corner: f64,
+}
+
+impl Board for RoundedRect {
+    fn bounds(&self) -> Rect {
+        self.rect
+    }
+    fn clearance(&self, point: Position2d) -> f64 {
+        let origin = self.rect.position();
+        let (half_w, half_h) = (self.rect.width() / 2.0, self.rect.height() / 2.0);
+        //Distance to the rectangle shrunk by the corner radius, less that radius.
+        let qx = (point.x - origin.x - half_w).abs() - (half_w - self.corner);
+        let qy = (point.y - origin.y - half_h).abs() - (half_h - self.corner);
+        let outside = qx.max(0.0).hypot(qy.max(0.0));
+        let inside = qx.max(qy).min(0.0);
+        self.corner - outside - inside
+    }
+    fn trace(&self, renderer: &mut dyn Renderer) {
+        let origin = self.rect.position();
+        let (left, top) = (origin.x, origin.y);
+        let (right, bottom) = (left + self.rect.width(), top + self.rect.height());
+        let r = self.corner;
+        renderer.arc(right - r, top + r, r, -PI / 2.0, 0.0);
+        renderer.arc(right - r, bottom - r, r, 0.0, PI / 2.0);
+        renderer.arc(left + r, bottom - r, r, PI / 2.0, PI);
+        renderer.arc(left + r, top + r, r, PI, PI * 1.5);
+        renderer.close_path();
+    }
+}
+
+/// Any polygon whose edges do not cross each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    points: Vec<Position2d>,
+}
+
+impl Polygon {
+    pub fn new(points: Vec<Position2d>) -> Result<Polygon, String> {
+        if points.len() < 3 {
+            return Err("A polygon needs at least 3 corners.".to_string());
+        }
+        let polygon = Polygon { points };
+        let count = polygon.points.len();
+        for i in 0..count {
+            //Neighbouring edges share a corner, so only the others may not meet.
+            for j in i + 2..count {
+                if i == 0 && j == count - 1 {
+                    continue;
+                }
+                let (a, b) = polygon.edge(i);
+                let (c, d) = polygon.edge(j);
+                if segments_cross(a, b, c, d) {
+                    return Err("The edges of a polygon cannot cross.".to_string());
+                }
+            }
+        }
+        if polygon.area() == 0.0 {
+            return Err("A polygon cannot be flat.".to_string());
+        }
+        Ok(polygon)
+    }
+    pub fn points(&self) -> &[Position2d] {
+        &self.points
+    }
+    fn edge(&self, idx: usize) -> (Position2d, Position2d) {
+        (self.points[idx], self.points[(idx + 1) % self.points.len()])
+    }
+    fn area(&self) -> f64 {
+        let twice: f64 = (0..self.points.len())
+            .map(|idx| {
+                let (a, b) = self.edge(idx);
+                a.x * b.y - b.x * a.y
+            })
+            .sum();
+        twice.abs() / 2.0
+    }
+    /// Whether `point` is inside, by counting the edges a ray to the right crosses.
+    fn encloses(&self, point: Position2d) -> bool {
+        let mut inside = false;
+        for idx in 0..self.points.len() {
+            let (a, b) = self.edge(idx);
+            if (a.y > point.y) != (b.y > point.y) {
+                let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+                if point.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+    fn bounds_of(points: impl Iterator<Item = Position2d>) -> Rect {
+        let (mut left, mut top) = (f64::INFINITY, f64::INFINITY);
+        let (mut right, mut bottom) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for p in points {
+            left = left.min(p.x);
+            top = top.min(p.y);
+            right = right.max(p.x);
+            bottom = bottom.max(p.y);
+        }
+        Rect::new(left, top, right - left, bottom - top)
+    }
+}
+
+impl Board for Polygon {
+    fn bounds(&self) -> Rect {
+        Polygon::bounds_of(self.points.iter().copied())
+    }
+    fn clearance(&self, point: Position2d) -> f64 {
+        let distance = (0..self.points.len())
+            .map(|idx| {
+                let (a, b) = self.edge(idx);
+                segment_distance(point, a, b)
+            })
+            .fold(f64::INFINITY, f64::min);
+        if self.encloses(point) {
+            distance
+        } else {
+            -distance
+        }
+    }
+    fn trace(&self, renderer: &mut dyn Renderer) {
+        renderer.move_to(self.points[0].x, self.points[0].y);
+        for p in self.points.iter().skip(1) {
+            renderer.line_to(p.x, p.y);
+        }
+        renderer.close_path();
+    }
+}
+
+fn segment_distance(p: Position2d, a: Position2d, b: Position2d) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((p.x - a.x) * dx + (p.y - a.y) * dy) / length).clamp(0.0, 1.0)
+    };
+    p.distance(&Position2d {
+        x: a.x + t * dx,
+        y: a.y + t * dy,
+    })
+}
+
+fn segments_cross(a: Position2d, b: Position2d, c: Position2d, d: Position2d) -> bool {
+    let side = |p: Position2d, q: Position2d, r: Position2d| {
+        ((q.x - p.x) * (r.y - p.y) - (q.y - p.y) * (r.x - p.x)).signum()
+    };
+    let (d1, d2) = (side(c, d, a), side(c, d, b));
+    let (d3, d4) = (side(a, b, c), side(a, b, d));
+    if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+        return true;
+    }
+    //Touching counts too, e.g. a corner lying on another edge.
+    let on = |p: Position2d, q: Position2d, r: Position2d| {
+        side(p, q, r) == 0.0
+            && r.x >= p.x.min(q.x)
+            && r.x <= p.x.max(q.x)
+            && r.y >= p.y.min(q.y)
+            && r.y <= p.y.max(q.y)
+    };
+    on(c, d, a) || on(c, d, b) || on(a, b, c) || on(a, b, d)
+}
+
+/// The board of a game: any of the shapes above.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameBoard {
+    Rect(Rect),
+    Circle(Circle),
+    RegularPolygon(RegularPolygon),
+    RoundedRect(RoundedRect),
+    Polygon(Polygon),
+}
+
+impl GameBoard {
+    /// A rectangle checked as one read from text, e.g. for saves that keep only the numbers.
+    pub fn rect(x: f64, y: f64, width: f64, height: f64) -> Result<GameBoard, String> {
+        let rect = checked_rect(x, y, width, height).ok_or_else(|| {
+            format!(
+                "A rectangle cannot be {}x{} at ({},{}).",
+                width, height, x, y
+            )
+        })?;
+        check_size(GameBoard::Rect(rect))
+    }
+    fn shape(&self) -> &dyn Board {
+        match self {
+            GameBoard::Rect(board) => board,
+            GameBoard::Circle(board) => board,
+            GameBoard::RegularPolygon(board) => board,
+            GameBoard::RoundedRect(board) => board,
+            GameBoard::Polygon(board) => board,
+        }
+    }
+}
+
+impl Board for GameBoard {
+    fn bounds(&self) -> Rect {
+        self.shape().bounds()
+    }
+    fn clearance(&self, point: Position2d) -> f64 {
+        self.shape().clearance(point)
+    }
+    fn contains(&self, circle: &Circle) -> bool {
+        self.shape().contains(circle)
+    }
+    fn trace(&self, renderer: &mut dyn Renderer) {
+        self.shape().trace(renderer)
+    }
+}
+
+impl From<Rect> for GameBoard {
+    fn from(rect: Rect) -> GameBoard {
+        GameBoard::Rect(rect)
+    }
+}
+
+impl Default for GameBoard {
+    /// The classic 1280x720 board.
+    fn default() -> GameBoard {
+        GameBoard::Rect(Rect::new(0.0, 0.0, 1280.0, 720.0))
+    }
+}
+
+fn write_rect(f: &mut fmt::Formatter, rect: &Rect) -> fmt::Result {
+    write!(f, "{}x{}", rect.width(), rect.height())?;
+    let origin = rect.position();
+    if origin.x != 0.0 || origin.y != 0.0 {
+        write!(f, "{:+}{:+}", origin.x, origin.y)?;
+    }
+    Ok(())
+}
+
+fn write_point(f: &mut fmt::Formatter, p: Position2d) -> fmt::Result {
+    write!(f, "({},{})", p.x, p.y)
+}
+
+impl fmt::Display for GameBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameBoard::Rect(rect) => write_rect(f, rect),
+            GameBoard::Circle(circle) => {
+                write!(f, "circle ")?;
+                write_point(f, circle.position())?;
+                write!(f, " r{}", circle.radius())
+            }
+            GameBoard::RegularPolygon(polygon) => {
+                write!(f, "regular {} ", polygon.sides)?;
+                write_point(f, polygon.center)?;
+                write!(f, " r{}", polygon.radius)?;
+                if polygon.rotation != 0.0 {
+                    write!(f, " a{}", polygon.rotation)?;
+                }
+                Ok(())
+            }
+            GameBoard::RoundedRect(rounded) => {
+                write!(f, "rounded ")?;
+                write_rect(f, &rounded.rect)?;
+                write!(f, " r{}", rounded.corner)
+            }
+            GameBoard::Polygon(polygon) => {
+                write!(f, "polygon")?;
+                for p in polygon.points() {
+                    write!(f, " ")?;
+                    write_point(f, *p)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Reads `WIDTHxHEIGHT` or `WIDTHxHEIGHT+X+Y`.
+fn parse_rect(value: &str) -> Result<Rect, String> {
+    let bad = || format!("A rectangle must look like 1280x720, not {}.", value);
+    let (width, rest) = value.split_once('x').ok_or_else(bad)?;
+    if rest.is_empty() {
+        return Err(bad());
+    }
+    let split = rest[1..]
+        .find(['+', '-'])
+        .map(|i| i + 1)
+        .unwrap_or(rest.len());
+    let (height, offset) = rest.split_at(split);
+    let (x, y) = if offset.is_empty() {
+        (0.0, 0.0)
+    } else {
+        let split = offset[1..].find(['+', '-']).ok_or_else(bad)? + 1;
+        let (x, y) = offset.split_at(split);
+        (x.parse().map_err(|_| bad())?, y.parse().map_err(|_| bad())?)
+    };
+    let width: f64 = width.parse().map_err(|_| bad())?;
+    let height: f64 = height.parse().map_err(|_| bad())?;
+    checked_rect(x, y, width, height).ok_or_else(bad)
+}
+
+/// A rectangle of finite numbers and positive size.
+fn checked_rect(x: f64, y: f64, width: f64, height: f64) -> Option<Rect> {
+    let finite = [x, y, width, height].iter().all(|n| n.is_finite());
+    (finite && width > 0.0 && height > 0.0).then(|| Rect::new(x, y, width, height))
+}
+
+/// Keeps `board` within `MAX_EXTENT` and `MAX_ASPECT`, so that the solver stays quick on it.
+fn check_size(board: GameBoard) -> Result<GameBoard, String> {
+    let bounds = board.bounds();
+    let origin = bounds.position();
+    let reach = [
+        origin.x,
+        origin.y,
+        origin.x + bounds.width(),
+        origin.y + bounds.height(),
+    ]
+    .iter()
+    .fold(0.0, |reach: f64, n| reach.max(n.abs()));
+    if reach.is_nan() || reach > MAX_EXTENT {
+        return Err(format!(
+            "A board can reach at most {} from the origin.",
+            MAX_EXTENT
+        ));
+    }
+    let (short, long) = (
+        bounds.width().min(bounds.height()),
+        bounds.width().max(bounds.height()),
+    );
+    if short.is_nan() || short <= 0.0 || long / short > MAX_ASPECT {
+        return Err(format!(
+            "A board can be at most {} times longer than it is wide.",
+            MAX_ASPECT
+        ));
+    }
+    Ok(board)
+}
+
+/// Reads `(X,Y)`.
+fn parse_point(value: &str) -> Result<Position2d, String> {
+    let bad = || format!("A point must look like (10,20), not {}.", value);
+    let (x, y) = value
+        .strip_prefix('(')
+        .and_then(|v| v.strip_suffix(')'))
+        .and_then(|v| v.split_once(','))
+        .ok_or_else(bad)?;
+    let point = Position2d {
+        x: x.trim().parse().map_err(|_| bad())?,
+        y: y.trim().parse().map_err(|_| bad())?,
+    };
+    if point.x.is_finite() && point.y.is_finite() {
+        Ok(point)
+    } else {
+        Err(bad())
+    }
+}
+
+/// Reads a positive number after `prefix`, as in `r360`.
+fn parse_prefixed(value: &str, prefix: char) -> Result<f64, String> {
+    let bad = || format!("Expected {}NUMBER, not {}.", prefix, value);
+    let number: f64 = value
+        .strip_prefix(prefix)
+        .ok_or_else(bad)?
+        .parse()
+        .map_err(|_| bad())?;
+    if number.is_finite() {
+        Ok(number)
+    } else {
+        Err(bad())
+    }
+}
+
+impl FromStr for GameBoard {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_shape(s).and_then(check_size)
+    }
+}
+
+/// Reads any of the shapes, before the size limits.
+fn parse_shape(s: &str) -> Result<GameBoard, String> {
+    let tokens: Vec<&str> = s.split_whitespace().collect();
+    let radius = |token: Option<&&str>| -> Result<f64, String> {
+        let radius = parse_prefixed(token.ok_or("A radius is missing.")?, 'r')?;
+        if radius > 0.0 {
+            Ok(radius)
+        } else {
+            Err("A radius must be positive.".to_string())
+        }
+    };
+    let point = |token: Option<&&str>| parse_point(token.ok_or("A center is missing.")?);
+    let extra = |count: usize| {
+        if tokens.len() > count {
+            Err(format!("Unexpected {} in the board.", tokens[count]))
+        } else {
+            Ok(())
+        }
+    };
+    match tokens.first().copied() {
+        None => Err("The board is empty.".to_string()),
+        Some("circle") => {
+            extra(3)?;
+            let center = point(tokens.get(1))?;
+            Ok(GameBoard::Circle(Circle::new(
+                center.x,
+                center.y,
+                radius(tokens.get(2))?,
+            )))
+        }
+        Some("regular") => {
+            extra(5)?;
+            let sides: u32 = tokens
+                .get(1)
+                .and_then(|t| t.parse().ok())
+                .filter(|&n| n >= 3)
+                .ok_or("A regular polygon needs 3 or more sides.")?;
+            if sides > MAX_SIDES {
+                return Err(format!(
+                    "A regular polygon can have at most {} sides.",
+                    MAX_SIDES
+                ));
+            }
+            let rotation = match tokens.get(4) {
+                Some(token) => parse_prefixed(token, 'a')?,
+                None => 0.0,
+            };
+            Ok(GameBoard::RegularPolygon(RegularPolygon {
+                center: point(tokens.get(2))?,
+                radius: radius(tokens.get(3))?,
+                sides,
+                rotation,
+            }))
+        }
+        Some("rounded") => {
+            extra(3)?;
+            let rect = parse_rect(tokens.get(1).ok_or("A rectangle is missing.")?)?;
+            let corner = radius(tokens.get(2))?;
+            if corner * 2.0 > rect.width().min(rect.height()) {
+                return Err("The corners are larger than the rectangle.".to_string());
+            }
+            Ok(GameBoard::RoundedRect(RoundedRect { rect, corner }))
+        }
+        Some("polygon") => {
+            if tokens.len() - 1 > MAX_POINTS {
+                return Err(format!(
+                    "A polygon can have at most {} corners.",
+                    MAX_POINTS
+                ));
+            }
+            let points = tokens[1..]
+                .iter()
+                .map(|t| parse_point(t))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(GameBoard::Polygon(Polygon::new(points)?))
+        }
+        Some(rect) => {
+            extra(1)?;
+            Ok(GameBoard::Rect(parse_rect(rect)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn parse(s: &str) -> GameBoard {
+        s.parse().unwrap()
+    }
+    #[test]
+    fn text_round_trip() {
+        for text in [
+            "1280x720",
+            "800x600-10+20",
+            "circle (360,360) r360",
+            "regular 6 (400,400) r300 a30",
+            "rounded 1280x720 r80",
+            "polygon (0,0) (600,0) (300,500)",
+        ] {
+            assert_eq!(parse(text).to_string(), text);
+        }
+        assert!("regular 2 (0,0) r10".parse::<GameBoard>().is_err());
+        assert!("rounded 100x100 r60".parse::<GameBoard>().is_err());
+        assert!("circle (0,0) r10 r20".parse::<GameBoard>().is_err());
+    }
+    #[test]
+    fn rejects_unbounded_boards() {
+        for text in [
+            "NaNx720",
+            "1280xinf",
+            "1280x720+inf+0",
+            "circle (NaN,0) r10",
+            "circle (0,0) rinf",
+            "circle (0,0) r0",
+            "regular 4000000000 (0,0) r10",
+            "regular 101 (0,0) r10",
+            "regular 6 (0,0) r10 aNaN",
+            "1e9x1",
+            "100000x10",
+            "1000x1000+99500+0",
+            "circle (0,0) r200000",
+            "polygon (0,0) (10000,0) (10000,1)",
+        ] {
+            assert!(text.parse::<GameBoard>().is_err(), "{}", text);
+        }
+        assert!("regular 100 (0,0) r10".parse::<GameBoard>().is_ok());
+        assert!("100000x1000".parse::<GameBoard>().is_ok());
+        assert_eq!(
+            GameBoard::rect(0.0, 0.0, 1280.0, 720.0),
+            Ok(GameBoard::default())
+        );
+        assert!(GameBoard::rect(0.0, 0.0, 0.0, 720.0).is_err());
+        assert!(GameBoard::rect(0.0, 0.0, 1e9, 720.0).is_err());
+        let many: Vec<String> = (0..=MAX_POINTS)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f64 / (MAX_POINTS + 1) as f64;
+                format!("({},{})", 100.0 * angle.cos(), 100.0 * angle.sin())
+            })
+            .collect();
+        let text = format!("polygon {}", many.join(" "));
+        assert!(text.parse::<GameBoard>().is_err());
+    }
+    #[test]
+    fn containment() {
+        let arena = parse("circle (100,100) r100");
+        assert!(arena.contains(&Circle::new(100.0, 150.0, 50.0)));
+        assert!(!arena.contains(&Circle::new(20.0, 20.0, 10.0)));
+        let hexagon = parse("regular 6 (100,100) r100");
+        //The apothem of the hexagon is 100 * cos(30°).
+        assert!(hexagon.contains(&Circle::new(100.0, 100.0, 86.0)));
+        assert!(!hexagon.contains(&Circle::new(100.0, 100.0, 87.0)));
+        //Past a corner, the corner itself is nearest, not the lines of the edges.
+        let diamond = parse("regular 4 (0,0) r10");
+        assert!((diamond.clearance(Position2d { x: 15.0, y: 0.0 }) + 5.0).abs() < 1e-9);
+        let obstacle: crate::map::Obstacle = "regular 4 (0,0) r10".parse().unwrap();
+        assert!(!obstacle.overlaps(&Circle::new(15.0, 0.0, 4.0)));
+        assert!(obstacle.overlaps(&Circle::new(15.0, 0.0, 6.0)));
+        let rounded = parse("rounded 200x100 r50");
+        assert!(rounded.contains(&Circle::new(50.0, 50.0, 50.0)));
+        assert!(!rounded.contains(&Circle::new(10.0, 10.0, 5.0)));
+        assert!(rounded.contains(&Circle::new(100.0, 10.0, 5.0)));
+        //An L shape.
+        let l = parse("polygon (0,0) (100,0) (100,40) (40,40) (40,100) (0,100)");
+        assert!(l.contains(&Circle::new(20.0, 80.0, 20.0)));
+        assert!(!l.contains(&Circle::new(70.0, 70.0, 10.0)));
+        assert!(!l.contains(&Circle::new(45.0, 45.0, 10.0)));
+        assert_eq!(l.bounds(), Rect::new(0.0, 0.0, 100.0, 100.0));
+    }
+    #[test]
+    fn rejects_crossing_polygons() {
+        let bowtie = "polygon (0,0) (100,100) (100,0) (0,100)";
+        assert!(bowtie.parse::<GameBoard>().is_err());
+        assert!("polygon (0,0) (10,10)".parse::<GameBoard>().is_err());
+        assert!("polygon (0,0) (10,10) (20,20)"
+            .parse::<GameBoard>()
+            .is_err());
+    }
+    #[test]
+    fn clearance_is_rect_margin() {
+        let rect = Rect::new(0.0, 0.0, 640.0, 480.0);
+        for circle in [
+            Circle::new(1.0, 1.0, 1.0),
+            Circle::new(0.0, 0.0, 1.0),
+            Circle::new(608.0, 449.0, 32.0),
+        ] {
+            assert_eq!(rect.contains(&circle), !rect.is_outside(&circle));
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use crate::board::Board;
+use crate::map::Map;
+use crate::shape::{clearance, largest_empty_circle, largest_empty_circles, Circle, Position2d};
+use std::fmt;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// How many of the roomiest spots a lookahead or random bot weighs.
+const OPTIONS: usize = 8;
+/// Random spots tried before settling for a known one.
+const RANDOM_ATTEMPTS: usize = 200;
+
+/// What a bot wants to do on its turn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Proposal {
+    Place(Circle),
+    GiveUp,
+}
+
+/// How a computer player picks its moves.
+pub trait Strategy {
+    /// Picks a move, given the board, the circles already on it and the radius limits.
+    /// A proposed circle must be valid; a bot that cannot find one should give up.
+    fn propose(
+        &mut self,
+        board: &Map,
+        circles: &[Circle],
+        min_radius: f64,
+        max_radius: f64,
+    ) -> Proposal;
+}
+
+/// Always takes the largest circle it can find.
+#[derive(Debug, Clone, Default)]
+pub struct Greedy;
+
+impl Strategy for Greedy {
+    fn propose(
+        &mut self,
+        board: &Map,
+        circles: &[Circle],
+        min_radius: f64,
+        max_radius: f64,
+    ) -> Proposal {
+        match largest(board, circles, min_radius, max_radius) {
+            Some(circle) => Proposal::Place(circle),
+            None => Proposal::GiveUp,
+        }
+    }
+}
+
+/// Places a valid circle of random size at a random spot.
+#[derive(Debug, Clone)]
+pub struct RandomValid {
+    rng: Rng,
+}
+
+impl RandomValid {
+    pub fn new(seed: u64) -> RandomValid {
+        RandomValid {
+            rng: Rng::new(seed),
+        }
+    }
+    /// A radius between `min_radius` and the room of `spot`.
+    fn shrink(&mut self, spot: Circle, min_radius: f64) -> Circle {
+        let min = min_radius.ceil();
+        let sizes = (spot.radius() - min) as u64 + 1;
+        let center = spot.position();
+        Circle::new(center.x, center.y, min + self.rng.below(sizes) as f64)
+    }
+}
+
+impl Strategy for RandomValid {
+    fn propose(
+        &mut self,
+        board: &Map,
+        circles: &[Circle],
+        min_radius: f64,
+        max_radius: f64,
+    ) -> Proposal {
+        let bounds = board.bounds();
+        let origin = bounds.position();
+        for _ in 0..RANDOM_ATTEMPTS {
+            let center = Position2d {
+                x: (origin.x + self.rng.below(bounds.width() as u64 + 1) as f64).round(),
+                y: (origin.y + self.rng.below(bounds.height() as u64 + 1) as f64).round(),
+            };
+            let spot = circle_at(board, circles, center, max_radius);
+            if spot.radius() >= min_radius {
+                return Proposal::Place(self.shrink(spot, min_radius));
+            }
+        }
+        //The board is crowded; choose among the roomiest spots left.
+        let spots = options(board, circles, min_radius, max_radius);
+        if spots.is_empty() {
+            return Proposal::GiveUp;
+        }
+        let spot = spots[self.rng.below(spots.len() as u64) as usize];
+        Proposal::Place(self.shrink(spot, min_radius))
+    }
+}
+
+/// Looks one move ahead, and leaves the opponents the smallest largest circle.
+/// Ties go to the larger circle of its own.
+#[derive(Debug, Clone, Default)]
+pub struct Lookahead;
+
+impl Strategy for Lookahead {
+    fn propose(
+        &mut self,
+        board: &Map,
+        circles: &[Circle],
+        min_radius: f64,
+        max_radius: f64,
+    ) -> Proposal {
+        let mut after = circles.to_vec();
+        let mut best: Option<(f64, Circle)> = None;
+        for option in options(board, circles, min_radius, max_radius) {
+            after.push(option);
+            let left = largest_empty_circle(board, &after, max_radius).map_or(0.0, |c| c.area());
+            after.pop();
+            let better = match best {
+                None => true,
+                Some((best_left, best_circle)) => {
+                    left < best_left
+                        || (left == best_left && option.radius() > best_circle.radius())
+                }
+            };
+            if better {
+                best = Some((left, option));
+            }
+        }
+        match best {
+            Some((_, circle)) => Proposal::Place(circle),
+            None => Proposal::GiveUp,
+        }
+    }
+}
+
+/// The built-in bots.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotKind {
+    Greedy,
+    Random,
+    Lookahead,
+}
+
+impl BotKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            BotKind::Greedy => "greedy",
+            BotKind::Random => "random",
+            BotKind::Lookahead => "lookahead",
+        }
+    }
+    /// A fresh strategy of this kind. `seed` makes random choices repeatable.
+    pub fn strategy(self, seed: u64) -> Box<dyn Strategy> {
+        match self {
+            BotKind::Greedy => Box::new(Greedy),
+            BotKind::Random => Box::new(RandomValid::new(seed)),
+            BotKind::Lookahead => Box::new(Lookahead),
+        }
+    }
+}
+
+impl fmt::Display for BotKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for BotKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [BotKind::Greedy, BotKind::Random, BotKind::Lookahead]
+            .iter()
+            .copied()
+            .find(|kind| kind.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown bot: {}", s))
+    }
+}
+
+/// A computer player sitting at a seat.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bot {
+    pub kind: BotKind,
+    /// Milliseconds to wait before each move, so people can follow.
+    pub think_delay: f64,
+}
+
+impl Bot {
+    pub fn new(kind: BotKind) -> Bot {
+        Bot {
+            kind,
+            think_delay: 800.0,
+        }
+    }
+}
+
+/// A tiny xorshift generator, good enough to keep bots unpredictable.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        //Xorshift gets stuck on zero.
+        Rng(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+    /// A number in `0..n`. `n` must not be zero.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+/// The largest whole radius circle centered at `center` that does not touch anything.
+/// Its radius is negative if there is no room at all.
+fn circle_at(board: &Map, circles: &[Circle], center: Position2d, max_radius: f64) -> Circle {
+    //Touching counts as overlapping, so stay just short of the room.
+    let radius = (clearance(board, circles, center) - 1e-9)
+        .floor()
+        .min(max_radius.floor());
+    Circle::new(center.x, center.y, radius)
+}
+
+/// `spot` moved to whole coordinates and shrunk until it can be placed.
+fn placeable(board: &Map, circles: &[Circle], spot: Circle, max_radius: f64) -> Circle {
+    let center = spot.position();
+    let center = Position2d {
+        x: center.x.round(),
+        y: center.y.round(),
+    };
+    circle_at(board, circles, center, max_radius)
+}
+
+/// The largest circle that could be placed, if any.
+fn largest(board: &Map, circles: &[Circle], min_radius: f64, max_radius: f64) -> Option<Circle> {
+    largest_empty_circle(board, circles, max_radius)
+        .map(|spot| placeable(board, circles, spot, max_radius))
+        .filter(|c| c.radius() >= min_radius)
+}
+
+/// The roomiest spots that could be placed, largest first.
+fn options(board: &Map, circles: &[Circle], min_radius: f64, max_radius: f64) -> Vec<Circle> {
+    largest_empty_circles(board, circles, max_radius, OPTIONS)
+        .into_iter()
+        .map(|spot| placeable(board, circles, spot, max_radius))
+        .filter(|c| c.radius() >= min_radius)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{validate, PlacedCircle};
+    fn board() -> Map {
+        Map::default()
+    }
+    fn placed(circles: &[Circle]) -> Vec<PlacedCircle> {
+        circles
+            .iter()
+            .map(|&circle| PlacedCircle { circle, owner: 0 })
+            .collect()
+    }
+    #[test]
+    fn greedy_fills_the_empty_board() {
+        let circle = match Greedy.propose(&board(), &[], 18.0, 300.0) {
+            Proposal::Place(circle) => circle,
+            Proposal::GiveUp => panic!("There is plenty of room."),
+        };
+        assert_eq!(circle.radius(), 300.0);
+        let full = [Circle::new(640.0, 360.0, 740.0)];
+        assert_eq!(
+            Greedy.propose(&board(), &full, 18.0, 300.0),
+            Proposal::GiveUp
+        );
+    }
+    #[test]
+    fn bots_play_valid_moves_until_the_end() {
+        let (min, max) = (60.0, 200.0);
+        let mut bots = [
+            BotKind::Random.strategy(7),
+            BotKind::Greedy.strategy(0),
+            BotKind::Lookahead.strategy(0),
+        ];
+        let mut circles = Vec::new();
+        let mut active = [true; 3];
+        let mut turn = 0;
+        while active.iter().any(|&a| a) {
+            if active[turn] {
+                match bots[turn].propose(&board(), &circles, min, max) {
+                    Proposal::Place(circle) => {
+                        assert_eq!(
+                            validate(&board(), &placed(&circles), &circle, min, max),
+                            Ok(())
+                        );
+                        circles.push(circle);
+                    }
+                    Proposal::GiveUp => active[turn] = false,
+                }
+            }
+            turn = (turn + 1) % 3;
+        }
+        assert!(circles.len() > 3);
+    }
+    #[test]
+    fn bots_stay_inside_other_boards() {
+        for name in ["islands", "pillars"] {
+            let board = Map::preset(name).unwrap();
+            let mut circles = Vec::new();
+            for kind in [BotKind::Greedy, BotKind::Random, BotKind::Lookahead] {
+                match kind.strategy(3).propose(&board, &circles, 30.0, 200.0) {
+                    Proposal::Place(circle) => {
+                        assert_eq!(
+                            validate(&board, &placed(&circles), &circle, 30.0, 200.0),
+                            Ok(())
+                        );
+                        circles.push(circle);
+                    }
+                    Proposal::GiveUp => panic!("There is plenty of room."),
+                }
+            }
+        }
+    }
+    #[test]
+    fn lookahead_leaves_no_more_room_than_greedy() {
+        let circles = [
+            Circle::new(300.0, 300.0, 200.0),
+            Circle::new(900.0, 400.0, 250.0),
+        ];
+        let room_after = |proposal| {
+            let mut after = circles.to_vec();
+            if let Proposal::Place(circle) = proposal {
+                after.push(circle);
+            }
+            largest_empty_circle(&board(), &after, 360.0).map_or(0.0, |c| c.area())
+        };
+        let greedy = room_after(Greedy.propose(&board(), &circles, 18.0, 360.0));
+        let lookahead = room_after(Lookahead.propose(&board(), &circles, 18.0, 360.0));
+        assert!(lookahead <= greedy);
+    }
+    #[test]
+    fn parse_kind() {
+        assert_eq!("Lookahead".parse(), Ok(BotKind::Lookahead));
+        assert!("clever".parse::<BotKind>().is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Time limits on the players, counted down by the front-end.
+//!
+//! The limits are written in seconds as `turn 30` for 30 s a turn,
+//! or `bank 300` for 5 min per player for the whole game, as with a chess clock.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// How much time the players have, in ms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeControl {
+    /// The same time for every turn.
+    PerTurn(f64),
+    /// A bank of time for the whole game, only spent on your own turns.
+    Bank(f64),
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeControl::PerTurn(ms) => write!(f, "turn {}", ms / 1000.0),
+            TimeControl::Bank(ms) => write!(f, "bank {}", ms / 1000.0),
+        }
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let kind = words.next().unwrap_or_default();
+        let seconds: f64 = match (words.next().map(str::parse), words.next()) {
+            (Some(Ok(seconds)), None) if seconds > 0.0 => seconds,
+            _ => return Err(format!("Bad time control: {}", s)),
+        };
+        match kind {
+            "turn" => Ok(TimeControl::PerTurn(seconds * 1000.0)),
+            "bank" => Ok(TimeControl::Bank(seconds * 1000.0)),
+            _ => Err(format!("Bad time control: {}", s)),
+        }
+    }
+}
+
+/// House rule for a player running out of time.
+/// Whatever the rule, a player gives up once nobody still in the game has time left,
+/// so that the game can end.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeoutRule {
+    /// The turn goes on to the next player, who stays in the game.
+    /// Older saves call it `Pass`.
+    #[serde(alias = "Pass")]
+    Skip,
+    /// The player gives up.
+    GiveUp,
+    /// Only the circle being drawn is lost, and the turn starts over.
+    /// Once the bank is empty, the turn is skipped instead.
+    DropDraft,
+}
+
+impl TimeoutRule {
+    pub fn name(self) -> &'static str {
+        match self {
+            TimeoutRule::Skip => "skip",
+            TimeoutRule::GiveUp => "give-up",
+            TimeoutRule::DropDraft => "drop",
+        }
+    }
+}
+
+impl FromStr for TimeoutRule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        //Older notation wrote `skip` as `pass`.
+        if s.trim().eq_ignore_ascii_case("pass") {
+            return Ok(TimeoutRule::Skip);
+        }
+        [
+            TimeoutRule::Skip,
+            TimeoutRule::GiveUp,
+            TimeoutRule::DropDraft,
+        ]
+        .iter()
+        .copied()
+        .find(|rule| rule.name().eq_ignore_ascii_case(s.trim()))
+        .ok_or_else(|| format!("Unknown rule: {}", s))
+    }
+}
+
+/// The time each player has left.
+#[derive(Debug, Clone)]
+pub struct Clock {
+    control: TimeControl,
+    /// In ms, by player.
+    left: Vec<f64>,
+    turn: Option<usize>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl, players: usize) -> Clock {
+        let full = match control {
+            TimeControl::PerTurn(ms) | TimeControl::Bank(ms) => ms,
+        };
+        Clock {
+            control,
+            left: vec![full; players],
+            turn: None,
+        }
+    }
+    pub fn left(&self, player: usize) -> f64 {
+        self.left[player]
+    }
+    /// Whether `player` has used up their whole bank. Never so for turn time.
+    pub fn bank_empty(&self, player: usize) -> bool {
+        matches!(self.control, TimeControl::Bank(_)) && self.left[player] == 0.0
+    }
+    /// Whose time is running, if anyone's.
+    pub fn turn(&self) -> Option<usize> {
+        self.turn
+    }
+    /// Starts the time of `player`, stopping everyone else's.
+    pub fn start_turn(&mut self, player: Option<usize>) {
+        self.turn = player;
+        self.restart_turn();
+    }
+    /// Gives the player on turn a full turn again, if time is given by the turn.
+    /// Returns whether they have any time now.
+    pub fn restart_turn(&mut self) -> bool {
+        match (self.control, self.turn) {
+            (TimeControl::PerTurn(ms), Some(player)) => {
+                self.left[player] = ms;
+                true
+            }
+            (TimeControl::Bank(_), Some(player)) => self.left[player] > 0.0,
+            (_, None) => false,
+        }
+    }
+    /// Lets `elapsed` ms pass. Returns the player on turn if they are out of time.
+    pub fn tick(&mut self, elapsed: f64) -> Option<usize> {
+        let player = self.turn?;
+        self.left[player] = (self.left[player] - elapsed).max(0.0);
+        (self.left[player] == 0.0).then_some(player)
+    }
+}
+
+/// Time left as `M:SS`, rounded up to the second.
+pub fn format_time(ms: f64) -> String {
+    let seconds = (ms / 1000.0).ceil() as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn turn_time_is_given_afresh() {
+        let mut clock = Clock::new(TimeControl::PerTurn(1000.0), 2);
+        assert_eq!(clock.tick(500.0), None);
+        clock.start_turn(Some(0));
+        assert_eq!(clock.tick(600.0), None);
+        assert_eq!(clock.tick(600.0), Some(0));
+        //It stays out until the next turn.
+        assert_eq!(clock.tick(0.0), Some(0));
+        clock.start_turn(Some(1));
+        clock.start_turn(Some(0));
+        assert_eq!(clock.left(0), 1000.0);
+    }
+    #[test]
+    fn bank_is_kept_between_turns() {
+        let mut clock = Clock::new(TimeControl::Bank(1000.0), 2);
+        clock.start_turn(Some(0));
+        clock.tick(700.0);
+        clock.start_turn(Some(1));
+        clock.tick(100.0);
+        clock.start_turn(Some(0));
+        assert_eq!(clock.left(0), 300.0);
+        assert_eq!(clock.left(1), 900.0);
+        assert_eq!(clock.tick(300.0), Some(0));
+        assert!(!clock.restart_turn());
+        assert!(clock.bank_empty(0) && !clock.bank_empty(1));
+    }
+    #[test]
+    fn text_forms() {
+        assert_eq!("turn 30".parse(), Ok(TimeControl::PerTurn(30000.0)));
+        assert_eq!(TimeControl::Bank(90500.0).to_string(), "bank 90.5");
+        assert!("bank".parse::<TimeControl>().is_err());
+        assert!("turn -3".parse::<TimeControl>().is_err());
+        assert_eq!(format_time(61001.0), "1:02");
+        assert_eq!(format_time(0.0), "0:00");
+        assert_eq!("skip".parse(), Ok(TimeoutRule::Skip));
+        //As written before the rule was renamed.
+        assert_eq!("pass".parse(), Ok(TimeoutRule::Skip));
+        assert_eq!(
+            serde_json::from_str::<TimeoutRule>("\"Pass\"").ok(),
+            Some(TimeoutRule::Skip)
+        );
+    }
+}
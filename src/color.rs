@@ -1,97 +1,399 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
+use std::str::FromStr;
+
+/// An sRGB color, possibly translucent.
+///
+/// Written as CSS: `#e5614a` when opaque, `rgba(229, 97, 74, 0.5)` otherwise.
+/// Read from a name such as `"red"`, or CSS hex, `rgb()`, `rgba()`, `hsl()` and `hsla()`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum Color {
-    Red,
-    Green,
-    Blue,
-    Yellow,
-    LightRed,
-    LightGreen,
-    LightBlue,
-    LightYellow,
+pub struct Color {
+    r: u8,
+    g: u8,
+    b: u8,
+    /// Opacity, from 0 for none to 255 for opaque.
+    a: u8,
 }
 
-impl fmt::Display for Color {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let color_str = match self {
-            Color::Red => "#e5614a",
-            Color::Green => "#5cd074",
-            Color::Blue => "#4aa3e3",
-            Color::Yellow => "#f5ca1a",
-            Color::LightRed => "#e69d91",
-            Color::LightGreen => "#99d1a4",
-            Color::LightBlue => "#91c1e3",
-            Color::LightYellow => "#f5de84",
+/// How far `to_light` brings the lightness to white.
+const LIGHTEN: f64 = 0.4;
+/// How much of its saturation `to_light` keeps.
+const LIGHT_SATURATION: f64 = 0.85;
+/// How much of its lightness `to_dark` keeps.
+const DARKEN: f64 = 0.75;
+
+impl Color {
+    pub const RED: Color = Color::from_hex(0xe5614a);
+    pub const GREEN: Color = Color::from_hex(0x5cd074);
+    pub const BLUE: Color = Color::from_hex(0x4aa3e3);
+    pub const YELLOW: Color = Color::from_hex(0xf5ca1a);
+    pub const PURPLE: Color = Color::from_hex(0x9b6ad6);
+    pub const ORANGE: Color = Color::from_hex(0xf08c35);
+    pub const CYAN: Color = Color::from_hex(0x3cc4c4);
+    pub const PINK: Color = Color::from_hex(0xe86bb0);
+    pub const LIGHT_RED: Color = Color::from_hex(0xe69d91);
+    pub const LIGHT_GREEN: Color = Color::from_hex(0x99d1a4);
+    pub const LIGHT_BLUE: Color = Color::from_hex(0x91c1e3);
+    pub const LIGHT_YELLOW: Color = Color::from_hex(0xf5de84);
+    pub const LIGHT_PURPLE: Color = Color::from_hex(0xc5ace6);
+    pub const LIGHT_ORANGE: Color = Color::from_hex(0xf5bf8f);
+    pub const LIGHT_CYAN: Color = Color::from_hex(0x93dede);
+    pub const LIGHT_PINK: Color = Color::from_hex(0xf0aed2);
+    pub const BLACK: Color = Color::from_hex(0x000000);
+    pub const WHITE: Color = Color::from_hex(0xffffff);
+
+    /// Colors a player can pick, in the default seating order.
+    pub const PLAYER_COLORS: [Color; 8] = [
+        Color::RED,
+        Color::GREEN,
+        Color::BLUE,
+        Color::YELLOW,
+        Color::PURPLE,
+        Color::ORANGE,
+        Color::CYAN,
+        Color::PINK,
+    ];
+    const NAMED: [(&'static str, Color); 16] = [
+        ("red", Color::RED),
+        ("green", Color::GREEN),
+        ("blue", Color::BLUE),
+        ("yellow", Color::YELLOW),
+        ("purple", Color::PURPLE),
+        ("orange", Color::ORANGE),
+        ("cyan", Color::CYAN),
+        ("pink", Color::PINK),
+        ("light-red", Color::LIGHT_RED),
+        ("light-green", Color::LIGHT_GREEN),
+        ("light-blue", Color::LIGHT_BLUE),
+        ("light-yellow", Color::LIGHT_YELLOW),
+        ("light-purple", Color::LIGHT_PURPLE),
+        ("light-orange", Color::LIGHT_ORANGE),
+        ("light-cyan", Color::LIGHT_CYAN),
+        ("light-pink", Color::LIGHT_PINK),
+    ];
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: 255 }
+    }
+    /// From `0xrrggbb`.
+    pub const fn from_hex(hex: u32) -> Color {
+        Color::rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+    }
+    /// From the hue in degrees, and the saturation and lightness from 0 to 1.
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Color {
+        let (s, l) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
         };
-        write!(f, "{}", color_str)
+        let m = l - chroma / 2.0;
+        let channel = |c: f64| ((c + m) * 255.0).round() as u8;
+        Color::rgb(channel(r), channel(g), channel(b))
     }
-}
-impl Color {
-    pub fn to_light(&self) -> Color {
-        match self {
-            Color::Red => Color::LightRed,
-            Color::Green => Color::LightGreen,
-            Color::Blue => Color::LightBlue,
-            Color::Yellow => Color::LightYellow,
-            _ => *self,
+    /// The hue in degrees, and the saturation and lightness from 0 to 1.
+    pub fn to_hsl(self) -> (f64, f64, f64) {
+        let [r, g, b] = [self.r, self.g, self.b].map(|c| f64::from(c) / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.0;
+        let d = max - min;
+        if d == 0.0 {
+            return (0.0, 0.0, l);
         }
+        let s = d / (1.0 - (2.0 * l - 1.0).abs());
+        let h = if max == r {
+            ((g - b) / d).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / d + 2.0
+        } else {
+            (r - g) / d + 4.0
+        };
+        (h * 60.0, s, l)
     }
-    pub fn to_dark(&self) -> Color {
-        match self {
-            Color::LightRed => Color::Red,
-            Color::LightGreen => Color::Green,
-            Color::LightBlue => Color::Blue,
-            Color::LightYellow => Color::Yellow,
-            _ => *self,
+    /// The red, green and blue channels.
+    pub fn channels(self) -> [u8; 3] {
+        [self.r, self.g, self.b]
+    }
+    /// From 0 for transparent to 1 for opaque.
+    pub fn alpha(self) -> f64 {
+        f64::from(self.a) / 255.0
+    }
+    /// The same color with the opacity `alpha`, from 0 to 1.
+    pub fn with_alpha(self, alpha: f64) -> Color {
+        Color {
+            a: (alpha.clamp(0.0, 1.0) * 255.0).round() as u8,
+            ..self
         }
     }
-    pub fn next(&self) -> Color {
-        match self {
-            Color::Red => Color::Green,
-            Color::Green => Color::Blue,
-            Color::Blue => Color::Yellow,
-            Color::Yellow => Color::Red,
-            Color::LightRed => Color::LightGreen,
-            Color::LightGreen => Color::LightBlue,
-            Color::LightBlue => Color::LightYellow,
-            Color::LightYellow => Color::LightRed,
+    /// A paler color of the same hue.
+    pub fn to_light(self) -> Color {
+        let (h, s, l) = self.to_hsl();
+        Color::from_hsl(h, s * LIGHT_SATURATION, l + (1.0 - l) * LIGHTEN).with_alpha(self.alpha())
+    }
+    /// A deeper color of the same hue.
+    pub fn to_dark(self) -> Color {
+        let (h, s, l) = self.to_hsl();
+        Color::from_hsl(h, s, l * DARKEN).with_alpha(self.alpha())
+    }
+    /// Half see-through.
+    pub fn to_translucent(self) -> Color {
+        self.with_alpha(self.alpha() / 2.0)
+    }
+    /// The gray just as bright.
+    pub fn to_gray(self) -> Color {
+        let luminance = self.luminance();
+        let encoded = if luminance <= 0.0031308 {
+            luminance * 12.92
+        } else {
+            1.055 * luminance.powf(1.0 / 2.4) - 0.055
+        };
+        let level = (encoded * 255.0).round() as u8;
+        Color::rgb(level, level, level).with_alpha(self.alpha())
+    }
+    /// Relative luminance as defined by WCAG, from 0 for black to 1 for white.
+    pub fn luminance(self) -> f64 {
+        let linear = |c: u8| {
+            let c = f64::from(c) / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+    /// The WCAG contrast ratio with `other`, from 1 for none to 21 for black on white.
+    pub fn contrast_ratio(self, other: Color) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+    /// The name of a named color, or its hex code otherwise.
+    pub fn name(self) -> String {
+        match Color::NAMED.iter().find(|(_, c)| *c == self) {
+            Some((name, _)) => name.to_string(),
+            None if self.a == 255 => format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b),
+            None => format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a),
+        }
+    }
+    /// The next player color, or for other colors the one an eighth of the way round the hues.
+    pub fn next(self) -> Color {
+        let seat = |colors: &[(&str, Color)]| colors.iter().position(|(_, c)| *c == self);
+        let (players, lights) = Color::NAMED.split_at(Color::PLAYER_COLORS.len());
+        if let Some(i) = seat(players) {
+            players[(i + 1) % players.len()].1
+        } else if let Some(i) = seat(lights) {
+            lights[(i + 1) % lights.len()].1
+        } else {
+            let (h, s, l) = self.to_hsl();
+            Color::from_hsl(h + 45.0, s, l).with_alpha(self.alpha())
         }
     }
 }
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.a == 255 {
+            write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            let alpha = (self.alpha() * 1000.0).round() / 1000.0;
+            write!(f, "rgba({}, {}, {}, {})", self.r, self.g, self.b, alpha)
+        }
+    }
+}
+
+/// Written in JSON as its name, e.g. `"red"` or `"#4aa3e3"`.
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name())
+    }
+}
+
+/// Read from JSON as any CSS color `FromStr` takes.
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Accepts a color name (`"red"`, `"light-blue"`, ...), or a CSS color
+/// in hex, `rgb()`, `rgba()`, `hsl()` or `hsla()`.
+impl FromStr for Color {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim().to_ascii_lowercase();
+        let error = || format!("Unknown color: {}", s.trim());
+        if let Some((_, color)) = Color::NAMED.iter().find(|(name, _)| *name == text) {
+            return Ok(*color);
+        }
+        if let Some(hex) = text.strip_prefix('#') {
+            return parse_hex(hex).ok_or_else(error);
+        }
+        let (function, args) = match text.strip_suffix(')').and_then(|t| t.split_once('(')) {
+            Some(parts) => parts,
+            None => return Err(error()),
+        };
+        let args: Vec<&str> = args
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|a| !a.is_empty())
+            .collect();
+        let alpha = match args.get(3) {
+            Some(alpha) => Some(parse_fraction(alpha, 1.0).ok_or_else(error)?),
+            None => None,
+        };
+        if args.len() < 3 || args.len() > 4 {
+            return Err(error());
+        }
+        let color = match function.trim() {
+            "rgb" | "rgba" => {
+                let channel = |arg: &str| {
+                    parse_fraction(arg, 255.0).map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8)
+                };
+                match (channel(args[0]), channel(args[1]), channel(args[2])) {
+                    (Some(r), Some(g), Some(b)) => Color::rgb(r, g, b),
+                    _ => return Err(error()),
+                }
+            }
+            "hsl" | "hsla" => {
+                let hue = args[0]
+                    .strip_suffix("deg")
+                    .unwrap_or(args[0])
+                    .parse::<f64>();
+                let percent = |arg: &str| parse_fraction(arg, 100.0);
+                match (hue, percent(args[1]), percent(args[2])) {
+                    (Ok(h), Some(s), Some(l)) if h.is_finite() => Color::from_hsl(h, s, l),
+                    _ => return Err(error()),
+                }
+            }
+            _ => return Err(error()),
+        };
+        Ok(color.with_alpha(alpha.unwrap_or(1.0)))
+    }
+}
+
+/// `rgb`, `rgba`, `rrggbb` or `rrggbbaa`.
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits: Vec<u8> = match hex.len() {
+        3 | 4 => hex
+            .chars()
+            .map(|c| u8::from_str_radix(&c.to_string().repeat(2), 16).ok())
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len() / 2)
+            .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    let color = Color::rgb(digits[0], digits[1], digits[2]);
+    Some(match digits.get(3) {
+        Some(&a) => Color { a, ..color },
+        None => color,
+    })
+}
+
+/// A number out of `full`, or a percentage, as a fraction.
+fn parse_fraction(arg: &str, full: f64) -> Option<f64> {
+    let (number, full) = match arg.strip_suffix('%') {
+        Some(number) => (number, 100.0),
+        None => (arg, full),
+    };
+    let value: f64 = number.parse().ok()?;
+    value.is_finite().then(|| (value / full).clamp(0.0, 1.0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     #[test]
     fn lighten() {
-        assert_eq!(Color::Red.to_light(), Color::LightRed);
-        assert_eq!(Color::Green.to_light(), Color::LightGreen);
-        assert_eq!(Color::Blue.to_light(), Color::LightBlue);
-        assert_eq!(Color::Yellow.to_light(), Color::LightYellow);
-        assert_eq!(Color::LightRed.to_light(), Color::LightRed);
-        assert_eq!(Color::LightGreen.to_light(), Color::LightGreen);
-        assert_eq!(Color::LightBlue.to_light(), Color::LightBlue);
-        assert_eq!(Color::LightYellow.to_light(), Color::LightYellow);
+        for color in Color::PLAYER_COLORS {
+            let (h, _, l) = color.to_hsl();
+            let (light_h, _, light_l) = color.to_light().to_hsl();
+            assert!(light_l > l);
+            assert!((light_h - h).abs() < 3.0, "{}", color);
+        }
+        assert_eq!(Color::WHITE.to_light(), Color::WHITE);
     }
     #[test]
     fn darken() {
-        assert_eq!(Color::Red.to_dark(), Color::Red);
-        assert_eq!(Color::Green.to_dark(), Color::Green);
-        assert_eq!(Color::Blue.to_dark(), Color::Blue);
-        assert_eq!(Color::Yellow.to_dark(), Color::Yellow);
-        assert_eq!(Color::LightRed.to_dark(), Color::Red);
-        assert_eq!(Color::LightGreen.to_dark(), Color::Green);
-        assert_eq!(Color::LightBlue.to_dark(), Color::Blue);
-        assert_eq!(Color::LightYellow.to_dark(), Color::Yellow);
+        for color in Color::PLAYER_COLORS {
+            let (h, _, l) = color.to_hsl();
+            let (dark_h, _, dark_l) = color.to_dark().to_hsl();
+            assert!(dark_l < l);
+            assert!((dark_h - h).abs() < 3.0, "{}", color);
+        }
+        assert_eq!(Color::BLACK.to_dark(), Color::BLACK);
+        assert_eq!(
+            Color::RED.to_translucent().to_string(),
+            "rgba(229, 97, 74, 0.502)"
+        );
     }
     #[test]
     fn next() {
-        assert_eq!(Color::Red.next(), Color::Green);
-        assert_eq!(Color::Green.next(), Color::Blue);
-        assert_eq!(Color::Blue.next(), Color::Yellow);
-        assert_eq!(Color::Yellow.next(), Color::Red);
-        assert_eq!(Color::LightRed.next(), Color::LightGreen);
-        assert_eq!(Color::LightGreen.next(), Color::LightBlue);
-        assert_eq!(Color::LightBlue.next(), Color::LightYellow);
-        assert_eq!(Color::LightYellow.next(), Color::LightRed);
+        assert_eq!(Color::RED.next(), Color::GREEN);
+        assert_eq!(Color::GREEN.next(), Color::BLUE);
+        assert_eq!(Color::PINK.next(), Color::RED);
+        assert_eq!(Color::LIGHT_RED.next(), Color::LIGHT_GREEN);
+        assert_eq!(Color::LIGHT_PINK.next(), Color::LIGHT_RED);
+        assert_eq!(
+            Color::from_hsl(0.0, 1.0, 0.5).next(),
+            Color::rgb(255, 191, 0)
+        );
+    }
+    #[test]
+    fn parse() {
+        assert_eq!("red".parse(), Ok(Color::RED));
+        assert_eq!(" Light-Blue ".parse(), Ok(Color::LIGHT_BLUE));
+        assert_eq!("#9B6AD6".parse(), Ok(Color::PURPLE));
+        assert_eq!("#f00".parse(), Ok(Color::rgb(255, 0, 0)));
+        assert_eq!("rgb(229, 97, 74)".parse(), Ok(Color::RED));
+        assert_eq!("rgb(100% 0% 0%)".parse(), Ok(Color::rgb(255, 0, 0)));
+        assert_eq!(
+            "rgba(0, 0, 255, 0.5)".parse(),
+            Ok(Color::rgb(0, 0, 255).with_alpha(0.5))
+        );
+        assert_eq!("hsl(120deg, 100%, 25%)".parse(), Ok(Color::rgb(0, 128, 0)));
+        assert_eq!(
+            "#0000ff80".parse(),
+            Ok(Color::rgb(0, 0, 255).with_alpha(0.5))
+        );
+        assert!("magenta".parse::<Color>().is_err());
+        assert!("rgb(1, 2)".parse::<Color>().is_err());
+        assert!("#12345".parse::<Color>().is_err());
+    }
+    #[test]
+    fn names_round_trip() {
+        let colors = [
+            Color::LIGHT_CYAN,
+            Color::rgb(1, 2, 3),
+            Color::rgb(1, 2, 3).with_alpha(0.25),
+        ];
+        for color in colors {
+            assert_eq!(color.name().parse(), Ok(color));
+            assert_eq!(
+                color.to_string().parse::<Color>().map(|c| c.channels()),
+                Ok(color.channels())
+            );
+        }
+        assert_eq!(Color::LIGHT_CYAN.name(), "light-cyan");
+    }
+    #[test]
+    fn hsl_and_contrast() {
+        for color in Color::PLAYER_COLORS {
+            let (h, s, l) = color.to_hsl();
+            assert_eq!(Color::from_hsl(h, s, l), color);
+        }
+        assert!((Color::BLACK.contrast_ratio(Color::WHITE) - 21.0).abs() < 1e-9);
+        assert_eq!(Color::RED.contrast_ratio(Color::RED), 1.0);
+        assert_eq!(Color::RED.to_gray().to_gray(), Color::RED.to_gray());
     }
 }
@@ -0,0 +1,729 @@
+use crate::board::{Board, GameBoard};
+use crate::bot::Bot;
+use crate::clock::{TimeControl, TimeoutRule};
+use crate::color::Color;
+use crate::history::{History, Move, UndoRule};
+use crate::i18n::Message;
+use crate::map::Map;
+use crate::scoring::ScoringRule;
+use crate::shape::{
+    largest_empty_circle, largest_empty_circles, Circle, Position2d, EMPTY_CIRCLE_TOLERANCE,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// Reasons why the engine refused an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameError {
+    Overlapped,
+    OutsideBoard,
+    OnObstacle,
+    TooSmall,
+    TooLarge,
+    NoDraft,
+    Finished,
+    NothingToUndo,
+    NothingToRedo,
+    NotYourTurn,
+}
+
+/// Written in the current locale.
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Message::Error(*self).fmt(f)
+    }
+}
+
+/// What happened as a result of an action, for the front-end to react on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    CirclePlaced {
+        player: usize,
+        circle: Circle,
+        score: f64,
+    },
+    PlayerGaveUp {
+        player: usize,
+        score: f64,
+    },
+    /// `player` let the turn go without placing a circle.
+    TurnSkipped {
+        player: usize,
+    },
+    /// The latest move of `player` was taken back.
+    MoveUndone {
+        player: usize,
+    },
+    /// No circle fits on the board any more. `player` was put out of the game
+    /// because of it, or the game was ended if `None`.
+    NoRoom {
+        player: Option<usize>,
+    },
+    /// `None` when no active player is left.
+    TurnChanged(Option<usize>),
+    /// Player indices from the winner to the last place.
+    GameOver(Vec<usize>),
+}
+
+/// House rule for when no circle of the minimum radius fits any more.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoRoomRule {
+    /// Players have to give up by themselves.
+    Manual,
+    /// Whoever would have to move next gives up automatically.
+    GiveUp,
+    /// The game ends at once, with the scores as they are.
+    EndGame,
+}
+
+impl NoRoomRule {
+    pub fn name(self) -> &'static str {
+        match self {
+            NoRoomRule::Manual => "manual",
+            NoRoomRule::GiveUp => "give-up",
+            NoRoomRule::EndGame => "end",
+        }
+    }
+}
+
+impl FromStr for NoRoomRule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [NoRoomRule::Manual, NoRoomRule::GiveUp, NoRoomRule::EndGame]
+            .iter()
+            .copied()
+            .find(|rule| rule.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown rule: {}", s))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Waiting for the current player to start a circle.
+    Idle,
+    /// The current player is dragging a circle.
+    Drawing,
+    Finished,
+}
+
+#[derive(Debug, Clone)]
+pub struct Player {
+    name: String,
+    color: Color,
+    active: bool,
+    score: f64,
+    /// `None` for a person.
+    bot: Option<Bot>,
+}
+
+impl Player {
+    pub fn new(name: &str, color: Color) -> Player {
+        Player {
+            name: name.to_string(),
+            color,
+            active: true,
+            score: 0.0,
+            bot: None,
+        }
+    }
+    /// A player part way through a game, e.g. read from a save.
+    pub fn restore(name: &str, color: Color, score: f64, active: bool) -> Player {
+        Player {
+            name: name.to_string(),
+            color,
+            active,
+            score,
+            bot: None,
+        }
+    }
+    /// The same seat, played by `bot` instead.
+    pub fn with_bot(self, bot: Option<Bot>) -> Player {
+        Player { bot, ..self }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn color(&self) -> Color {
+        self.color
+    }
+    pub fn active(&self) -> bool {
+        self.active
+    }
+    /// Kept exact; round it only to show it.
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+    pub fn bot(&self) -> Option<Bot> {
+        self.bot
+    }
+    fn deactivate(&mut self) {
+        self.active = false;
+    }
+    fn activate(&mut self) {
+        self.active = true;
+    }
+    fn set_score(&mut self, score: f64) {
+        self.score = score;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedCircle {
+    pub circle: Circle,
+    pub owner: usize,
+}
+
+pub fn validate(
+    map: &Map,
+    circles: &[PlacedCircle],
+    current_circle: &Circle,
+    min_radius: f64,
+    max_radius: f64,
+) -> Result<(), GameError> {
+    let radius = current_circle.radius();
+    if circles
+        .iter()
+        .any(|c| c.circle.is_overlapped(current_circle))
+    {
+        Err(GameError::Overlapped)
+    } else if !map.board().contains(current_circle) {
+        Err(GameError::OutsideBoard)
+    } else if map.obstacles().iter().any(|o| o.overlaps(current_circle)) {
+        Err(GameError::OnObstacle)
+    } else if min_radius > radius {
+        Err(GameError::TooSmall)
+    } else if radius > max_radius {
+        Err(GameError::TooLarge)
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns:\
+/// `Some(usize)` Index of the next active player. (include themselves.)
+/// `None` if none of the players active.
+pub fn next_player_idx(players: &[Player], current_idx: Option<usize>) -> Option<usize> {
+    if let Some(idx) = current_idx {
+        let number_of_players = players.len();
+        for i in 0..number_of_players {
+            let next_idx = (idx + 1 + i) % number_of_players;
+            if players[next_idx].active() {
+                return Some(next_idx);
+            }
+        }
+    }
+    None
+}
+
+/// Rules and state of a single match, independent of any DOM.
+#[derive(Debug, Clone)]
+pub struct Game {
+    map: Map,
+    min_radius: f64,
+    max_radius: f64,
+    players: Vec<Player>,
+    circles: Vec<PlacedCircle>,
+    current_player_idx: Option<usize>,
+    draft: Option<Circle>,
+    draft_result: Result<(), GameError>,
+    history: History,
+    no_room_rule: NoRoomRule,
+    scoring_rule: ScoringRule,
+    time_control: Option<TimeControl>,
+    timeout_rule: TimeoutRule,
+    /// Players put out for lack of room, with the number of moves played at that time.
+    out_of_room: Vec<(usize, usize)>,
+    /// Competitive play, where no help such as hints is given.
+    ranked: bool,
+}
+
+impl Game {
+    pub fn new(
+        map: impl Into<Map>,
+        min_radius: f64,
+        max_radius: f64,
+        players: Vec<Player>,
+    ) -> Game {
+        let current_player_idx = if players.is_empty() { None } else { Some(0) };
+        Game {
+            map: map.into(),
+            min_radius,
+            max_radius,
+            players,
+            circles: Vec::new(),
+            current_player_idx,
+            draft: None,
+            draft_result: Ok(()),
+            history: History::new(UndoRule::Unlimited),
+            no_room_rule: NoRoomRule::EndGame,
+            scoring_rule: ScoringRule::Area,
+            time_control: None,
+            timeout_rule: TimeoutRule::Skip,
+            out_of_room: Vec::new(),
+            ranked: false,
+        }
+    }
+    /// Continues from a position part way through, e.g. read from a save.
+    /// The moves that led there are unknown, so they cannot be undone.
+    pub fn resume(mut self, circles: Vec<PlacedCircle>, current_player_idx: Option<usize>) -> Game {
+        self.circles = circles;
+        self.current_player_idx = current_player_idx;
+        self.rescore();
+        self
+    }
+    pub fn set_undo_rule(&mut self, rule: UndoRule) {
+        self.history = History::new(rule);
+    }
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+    pub fn set_no_room_rule(&mut self, rule: NoRoomRule) {
+        self.no_room_rule = rule;
+    }
+    pub fn no_room_rule(&self) -> NoRoomRule {
+        self.no_room_rule
+    }
+    pub fn set_scoring_rule(&mut self, rule: ScoringRule) {
+        self.scoring_rule = rule;
+        self.rescore();
+    }
+    pub fn scoring_rule(&self) -> ScoringRule {
+        self.scoring_rule
+    }
+    /// Limits the time of the players, or lifts the limit if `None`.
+    /// The clock itself is kept by the front-end.
+    pub fn set_time_control(&mut self, control: Option<TimeControl>) {
+        self.time_control = control;
+    }
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.time_control
+    }
+    pub fn set_timeout_rule(&mut self, rule: TimeoutRule) {
+        self.timeout_rule = rule;
+    }
+    pub fn timeout_rule(&self) -> TimeoutRule {
+        self.timeout_rule
+    }
+    pub fn set_ranked(&mut self, ranked: bool) {
+        self.ranked = ranked;
+    }
+    pub fn ranked(&self) -> bool {
+        self.ranked
+    }
+    /// Players that gave up automatically, rather than by a move of their own.
+    pub fn out_of_room(&self) -> impl Iterator<Item = usize> + '_ {
+        self.out_of_room.iter().map(|&(_, player)| player)
+    }
+    /// The board and its obstacles.
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+    pub fn board(&self) -> &GameBoard {
+        self.map.board()
+    }
+    pub fn min_radius(&self) -> f64 {
+        self.min_radius
+    }
+    pub fn max_radius(&self) -> f64 {
+        self.max_radius
+    }
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+    pub fn circles(&self) -> &[PlacedCircle] {
+        &self.circles
+    }
+    pub fn current_player_idx(&self) -> Option<usize> {
+        self.current_player_idx
+    }
+    pub fn current_player(&self) -> Option<&Player> {
+        self.current_player_idx.map(|idx| &self.players[idx])
+    }
+    /// The circle being dragged, and whether it could be placed as it is.
+    pub fn draft(&self) -> Option<(&Circle, Result<(), GameError>)> {
+        self.draft.as_ref().map(|c| (c, self.draft_result))
+    }
+    pub fn phase(&self) -> Phase {
+        match (self.current_player_idx, self.draft) {
+            (None, _) => Phase::Finished,
+            (Some(_), None) => Phase::Idle,
+            (Some(_), Some(_)) => Phase::Drawing,
+        }
+    }
+    /// Whether a circle of the minimum radius may still fit.
+    /// Only says no when sure, so a gap within `EMPTY_CIRCLE_TOLERANCE` of fitting counts as room.
+    pub fn has_room(&self) -> bool {
+        let circles: Vec<Circle> = self.circles.iter().map(|c| c.circle).collect();
+        //Nothing larger than the minimum matters, which keeps the search short.
+        let cap = self.min_radius + EMPTY_CIRCLE_TOLERANCE;
+        let room = largest_empty_circle(&self.map, &circles, cap).map_or(0.0, |c| c.radius());
+        room + EMPTY_CIRCLE_TOLERANCE > self.min_radius
+    }
+    /// Up to `count` of the largest circles that could still be placed, largest first.
+    /// They do not overlap each other, so each one points at a different gap.
+    pub fn largest_spots(&self, count: usize) -> Vec<Circle> {
+        let circles: Vec<Circle> = self.circles.iter().map(|c| c.circle).collect();
+        largest_empty_circles(&self.map, &circles, self.max_radius, count)
+            .into_iter()
+            .filter(|c| c.radius() >= self.min_radius)
+            .collect()
+    }
+    pub fn validate(&self, circle: &Circle) -> Result<(), GameError> {
+        validate(
+            &self.map,
+            &self.circles,
+            circle,
+            self.min_radius,
+            self.max_radius,
+        )
+    }
+
+    /// Starts a new circle of the current player centered at `center`.
+    pub fn begin_circle(&mut self, center: Position2d) -> Result<(), GameError> {
+        if self.current_player_idx.is_none() {
+            return Err(GameError::Finished);
+        }
+        let circle = Circle::new(center.x, center.y, 0.0);
+        self.draft_result = self.validate(&circle);
+        self.draft = Some(circle);
+        Ok(())
+    }
+    /// Stretches the circle being drawn so that its edge passes `edge`.
+    pub fn resize_circle(&mut self, edge: Position2d) -> Result<(), GameError> {
+        let mut circle = self.draft.ok_or(GameError::NoDraft)?;
+        circle.set_radius(circle.position().distance(&edge).floor());
+        self.draft_result = self.validate(&circle);
+        self.draft = Some(circle);
+        self.draft_result
+    }
+    /// Drops the circle being drawn, if any.
+    pub fn cancel_circle(&mut self) -> Option<Circle> {
+        self.draft_result = Ok(());
+        self.draft.take()
+    }
+    /// Places the circle being drawn and passes the turn.
+    /// An invalid circle is discarded and the turn is kept.
+    pub fn commit_circle(&mut self) -> Result<Vec<GameEvent>, GameError> {
+        let idx = self.current_player_idx.ok_or(GameError::Finished)?;
+        let circle = self.cancel_circle().ok_or(GameError::NoDraft)?;
+        self.play_move(Move::Place {
+            player: idx,
+            circle,
+        })
+    }
+    /// The current player gives up for the rest of the game.
+    pub fn give_up(&mut self) -> Result<Vec<GameEvent>, GameError> {
+        let idx = self.current_player_idx.ok_or(GameError::Finished)?;
+        self.play_move(Move::GiveUp { player: idx })
+    }
+    /// The current player lets the turn go, but stays in the game.
+    pub fn skip(&mut self) -> Result<Vec<GameEvent>, GameError> {
+        let idx = self.current_player_idx.ok_or(GameError::Finished)?;
+        self.play_move(Move::Skip { player: idx })
+    }
+    /// Plays a whole move at once, as if the current player had drawn it.
+    pub fn play_move(&mut self, m: Move) -> Result<Vec<GameEvent>, GameError> {
+        let idx = self.current_player_idx.ok_or(GameError::Finished)?;
+        if m.player() != idx {
+            return Err(GameError::NotYourTurn);
+        }
+        self.cancel_circle();
+        if let Move::Place { circle, .. } = m {
+            self.validate(&circle)?;
+        }
+        Ok(self.play(m))
+    }
+    /// Takes the latest move back, as far as the undo rule allows,
+    /// and gives the turn back to whoever made it.
+    pub fn undo(&mut self) -> Result<Vec<GameEvent>, GameError> {
+        let m = self.history.undo().ok_or(GameError::NothingToUndo)?;
+        self.cancel_circle();
+        //Whoever was put out by the move is back in.
+        let played = self.history.moves().len();
+        while let Some(&(stamp, player)) = self.out_of_room.last() {
+            if stamp <= played {
+                break;
+            }
+            self.players[player].activate();
+            self.out_of_room.pop();
+        }
+        match m {
+            Move::Place { .. } => {
+                self.circles.pop();
+                self.rescore();
+            }
+            Move::GiveUp { player } => self.players[player].activate(),
+            Move::Skip { .. } => {}
+        }
+        self.current_player_idx = Some(m.player());
+        Ok(vec![
+            GameEvent::MoveUndone { player: m.player() },
+            GameEvent::TurnChanged(self.current_player_idx),
+        ])
+    }
+    /// Plays the latest undone move again.
+    pub fn redo(&mut self) -> Result<Vec<GameEvent>, GameError> {
+        let m = self.history.redo().ok_or(GameError::NothingToRedo)?;
+        self.cancel_circle();
+        Ok(self.apply(m))
+    }
+    /// Player indices sorted from the best score to the worst:
+    /// the highest first, or the lowest if the scoring rule says so.
+    /// Ties keep the seating order.
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut ranking: Vec<usize> = (0..self.players.len()).collect();
+        let score = |idx: usize| self.players[idx].score();
+        if self.scoring_rule.lowest_wins() {
+            ranking.sort_by(|&a, &b| score(a).total_cmp(&score(b)));
+        } else {
+            ranking.sort_by(|&a, &b| score(b).total_cmp(&score(a)));
+        }
+        ranking
+    }
+
+    /// Works the scores out again from the circles on the board.
+    fn rescore(&mut self) {
+        let scores = self
+            .scoring_rule
+            .scores(&self.map, &self.circles, self.players.len());
+        for (player, score) in self.players.iter_mut().zip(scores) {
+            player.set_score(score);
+        }
+    }
+    fn play(&mut self, m: Move) -> Vec<GameEvent> {
+        self.history.record(m);
+        self.apply(m)
+    }
+    fn apply(&mut self, m: Move) -> Vec<GameEvent> {
+        let mut events = match m {
+            Move::Place { player, circle } => {
+                self.circles.push(PlacedCircle {
+                    circle,
+                    owner: player,
+                });
+                self.rescore();
+                vec![GameEvent::CirclePlaced {
+                    player,
+                    circle,
+                    score: self.players[player].score(),
+                }]
+            }
+            Move::GiveUp { player } => {
+                self.players[player].deactivate();
+                vec![GameEvent::PlayerGaveUp {
+                    player,
+                    score: self.players[player].score(),
+                }]
+            }
+            Move::Skip { player } => vec![GameEvent::TurnSkipped { player }],
+        };
+        self.current_player_idx = Some(m.player());
+        self.advance_turn(&mut events);
+        events
+    }
+    fn advance_turn(&mut self, events: &mut Vec<GameEvent>) {
+        let mut next = next_player_idx(&self.players, self.current_player_idx);
+        if next.is_some() && self.no_room_rule != NoRoomRule::Manual && !self.has_room() {
+            if self.no_room_rule == NoRoomRule::EndGame {
+                events.push(GameEvent::NoRoom { player: None });
+                next = None;
+            }
+            while let Some(idx) = next {
+                self.players[idx].deactivate();
+                self.out_of_room.push((self.history.moves().len(), idx));
+                events.push(GameEvent::NoRoom { player: Some(idx) });
+                events.push(GameEvent::PlayerGaveUp {
+                    player: idx,
+                    score: self.players[idx].score(),
+                });
+                next = next_player_idx(&self.players, Some(idx));
+            }
+        }
+        self.current_player_idx = next;
+        events.push(GameEvent::TurnChanged(next));
+        if next.is_none() {
+            events.push(GameEvent::GameOver(self.ranking()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::Rect;
+    fn two_player_game() -> Game {
+        Game::new(
+            Rect::new(0.0, 0.0, 1280.0, 720.0),
+            18.0,
+            300.0,
+            vec![Player::new("R", Color::RED), Player::new("G", Color::GREEN)],
+        )
+    }
+    fn place(game: &mut Game, x: f64, y: f64, radius: f64) -> Result<Vec<GameEvent>, GameError> {
+        game.begin_circle(Position2d { x, y })?;
+        let _ = game.resize_circle(Position2d { x: x + radius, y });
+        game.commit_circle()
+    }
+    #[test]
+    fn placing_passes_the_turn() {
+        let mut game = two_player_game();
+        assert_eq!(game.phase(), Phase::Idle);
+        game.begin_circle(Position2d { x: 100.0, y: 100.0 })
+            .unwrap();
+        assert_eq!(game.phase(), Phase::Drawing);
+        let events = place(&mut game, 100.0, 100.0, 50.0).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1], GameEvent::TurnChanged(Some(1)));
+        assert_eq!(game.players()[0].score() as u32, 7853);
+        assert_eq!(game.circles().len(), 1);
+        assert_eq!(game.circles()[0].owner, 0);
+    }
+    #[test]
+    fn invalid_circle_keeps_the_turn() {
+        let mut game = two_player_game();
+        place(&mut game, 100.0, 100.0, 50.0).unwrap();
+        assert_eq!(
+            place(&mut game, 150.0, 100.0, 20.0),
+            Err(GameError::Overlapped)
+        );
+        assert_eq!(
+            place(&mut game, 10.0, 10.0, 20.0),
+            Err(GameError::OutsideBoard)
+        );
+        assert_eq!(
+            place(&mut game, 500.0, 300.0, 5.0),
+            Err(GameError::TooSmall)
+        );
+        assert_eq!(
+            place(&mut game, 640.0, 360.0, 320.0),
+            Err(GameError::TooLarge)
+        );
+        assert_eq!(game.commit_circle(), Err(GameError::NoDraft));
+        assert_eq!(game.current_player_idx(), Some(1));
+        assert_eq!(game.circles().len(), 1);
+    }
+    #[test]
+    fn game_ends_when_everyone_gives_up() {
+        let mut game = two_player_game();
+        place(&mut game, 100.0, 100.0, 50.0).unwrap();
+        place(&mut game, 400.0, 400.0, 100.0).unwrap();
+        let events = game.give_up().unwrap();
+        assert_eq!(events[1], GameEvent::TurnChanged(Some(1)));
+        game.begin_circle(Position2d { x: 900.0, y: 300.0 })
+            .unwrap();
+        let events = game.give_up().unwrap();
+        assert_eq!(game.phase(), Phase::Finished);
+        assert_eq!(events.last(), Some(&GameEvent::GameOver(vec![1, 0])));
+        assert_eq!(game.give_up(), Err(GameError::Finished));
+        assert_eq!(
+            game.begin_circle(Position2d { x: 0.0, y: 0.0 }),
+            Err(GameError::Finished)
+        );
+    }
+    fn cramped_game(rule: NoRoomRule) -> Game {
+        let mut game = Game::new(
+            Rect::new(0.0, 0.0, 100.0, 100.0),
+            20.0,
+            50.0,
+            vec![Player::new("R", Color::RED), Player::new("G", Color::GREEN)],
+        );
+        game.set_no_room_rule(rule);
+        game
+    }
+    #[test]
+    fn no_room_ends_the_game() {
+        let mut game = cramped_game(NoRoomRule::EndGame);
+        assert!(game.has_room());
+        //Only the corners are left, and they are too small.
+        let events = place(&mut game, 50.0, 50.0, 45.0).unwrap();
+        assert!(!game.has_room());
+        assert!(events.contains(&GameEvent::NoRoom { player: None }));
+        assert_eq!(events.last(), Some(&GameEvent::GameOver(vec![0, 1])));
+        assert!(game.players().iter().all(|p| p.active()));
+        game.undo().unwrap();
+        assert_eq!(game.phase(), Phase::Idle);
+        game.redo().unwrap();
+        assert_eq!(game.phase(), Phase::Finished);
+    }
+    #[test]
+    fn no_room_puts_players_out() {
+        let mut game = cramped_game(NoRoomRule::GiveUp);
+        let events = place(&mut game, 50.0, 50.0, 45.0).unwrap();
+        assert_eq!(events[1], GameEvent::NoRoom { player: Some(1) });
+        assert_eq!(game.out_of_room().collect::<Vec<_>>(), vec![1, 0]);
+        assert_eq!(game.phase(), Phase::Finished);
+        game.undo().unwrap();
+        assert!(game.players().iter().all(|p| p.active()));
+        assert_eq!(game.out_of_room().count(), 0);
+
+        let mut game = cramped_game(NoRoomRule::Manual);
+        place(&mut game, 50.0, 50.0, 45.0).unwrap();
+        assert_eq!(game.current_player_idx(), Some(1));
+    }
+    #[test]
+    fn skipping_keeps_the_player_in() {
+        let mut game = two_player_game();
+        let events = game.skip().unwrap();
+        assert_eq!(events[0], GameEvent::TurnSkipped { player: 0 });
+        assert_eq!(game.current_player_idx(), Some(1));
+        game.give_up().unwrap();
+        assert_eq!(game.current_player_idx(), Some(0));
+        assert!(game.players()[0].active());
+        game.undo().unwrap();
+        game.undo().unwrap();
+        assert_eq!(game.current_player_idx(), Some(0));
+    }
+    #[test]
+    fn scoring_rule_decides_the_ranking() {
+        let mut game = two_player_game();
+        place(&mut game, 100.0, 100.0, 50.0).unwrap();
+        place(&mut game, 400.0, 400.0, 30.0).unwrap();
+        place(&mut game, 600.0, 100.0, 20.0).unwrap();
+        assert_eq!(game.ranking(), vec![0, 1]);
+        game.set_scoring_rule(ScoringRule::Count);
+        assert_eq!(game.players()[0].score(), 2.0);
+        game.set_scoring_rule(ScoringRule::Smallest);
+        assert_eq!(game.ranking(), vec![1, 0]);
+        game.undo().unwrap();
+        assert_eq!(
+            game.players()[0].score(),
+            Circle::new(0.0, 0.0, 50.0).area()
+        );
+    }
+    #[test]
+    fn obstacles_block_circles() {
+        let mut game = Game::new(
+            Map::preset("pillars").unwrap(),
+            18.0,
+            300.0,
+            vec![Player::new("R", Color::RED), Player::new("G", Color::GREEN)],
+        );
+        assert_eq!(
+            place(&mut game, 320.0, 180.0, 20.0),
+            Err(GameError::OnObstacle)
+        );
+        //Touching a pillar is fine.
+        place(&mut game, 320.0, 300.0, 60.0).unwrap();
+        assert!(game.largest_spots(3).iter().all(|spot| game
+            .map()
+            .obstacles()
+            .iter()
+            .all(|o| !o.overlaps(spot))));
+    }
+    #[test]
+    fn largest_spots_point_at_gaps() {
+        let mut game = two_player_game();
+        let spots = game.largest_spots(3);
+        assert_eq!(spots[0].radius(), 300.0);
+        place(&mut game, 640.0, 360.0, 300.0).unwrap();
+        let spots = game.largest_spots(3);
+        assert_eq!(spots.len(), 3);
+        //The circle in the middle leaves the most room in the two side strips.
+        assert!(spots[0].position().x < 340.0 || spots[0].position().x > 940.0);
+        assert!(spots.iter().all(|s| s.radius() >= game.min_radius()));
+        assert!(cramped_game(NoRoomRule::Manual).largest_spots(1).len() == 1);
+    }
+}
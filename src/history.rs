@@ -0,0 +1,169 @@
+use crate::shape::Circle;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// A single turn, which can be taken back and played again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Move {
+    Place {
+        player: usize,
+        circle: Circle,
+    },
+    GiveUp {
+        player: usize,
+    },
+    /// The turn goes on without a circle, e.g. when time ran out.
+    Skip {
+        player: usize,
+    },
+}
+
+impl Move {
+    /// Whose turn it was.
+    pub fn player(&self) -> usize {
+        match self {
+            Move::Place { player, .. } | Move::GiveUp { player } | Move::Skip { player } => *player,
+        }
+    }
+}
+
+/// House rule on taking moves back.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UndoRule {
+    Disabled,
+    /// Only the latest move can be taken back.
+    LastMove,
+    /// Like `LastMove`, but the other players have to agree first.
+    LastMoveByConsent,
+    Unlimited,
+}
+
+impl UndoRule {
+    pub fn needs_consent(self) -> bool {
+        self == UndoRule::LastMoveByConsent
+    }
+}
+
+/// Moves played so far, and the ones taken back that can still be redone.
+#[derive(Debug, Clone)]
+pub struct History {
+    rule: UndoRule,
+    done: Vec<Move>,
+    undone: Vec<Move>,
+}
+
+impl History {
+    pub fn new(rule: UndoRule) -> History {
+        History {
+            rule,
+            done: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+    pub fn rule(&self) -> UndoRule {
+        self.rule
+    }
+    /// Moves in the order they were played.
+    pub fn moves(&self) -> &[Move] {
+        &self.done
+    }
+    pub fn can_undo(&self) -> bool {
+        match self.rule {
+            UndoRule::Disabled => false,
+            UndoRule::LastMove | UndoRule::LastMoveByConsent => {
+                !self.done.is_empty() && self.undone.is_empty()
+            }
+            UndoRule::Unlimited => !self.done.is_empty(),
+        }
+    }
+    pub fn can_redo(&self) -> bool {
+        self.rule != UndoRule::Disabled && !self.undone.is_empty()
+    }
+    /// Records a freshly played move. It forgets the moves that were undone.
+    pub(crate) fn record(&mut self, m: Move) {
+        self.undone.clear();
+        self.done.push(m);
+    }
+    pub(crate) fn undo(&mut self) -> Option<Move> {
+        if !self.can_undo() {
+            return None;
+        }
+        let m = self.done.pop()?;
+        self.undone.push(m);
+        Some(m)
+    }
+    pub(crate) fn redo(&mut self) -> Option<Move> {
+        if !self.can_redo() {
+            return None;
+        }
+        let m = self.undone.pop()?;
+        self.done.push(m);
+        Some(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::game::{Game, GameError, GameEvent, Player};
+    use crate::shape::{Position2d, Rect};
+    fn game(rule: UndoRule) -> Game {
+        let mut game = Game::new(
+            Rect::new(0.0, 0.0, 1280.0, 720.0),
+            18.0,
+            360.0,
+            vec![Player::new("R", Color::RED), Player::new("G", Color::GREEN)],
+        );
+        game.set_undo_rule(rule);
+        game
+    }
+    fn place(game: &mut Game, x: f64, y: f64, radius: f64) {
+        game.begin_circle(Position2d { x, y }).unwrap();
+        game.resize_circle(Position2d { x: x + radius, y }).unwrap();
+        game.commit_circle().unwrap();
+    }
+    #[test]
+    fn undo_and_redo_restore_the_turn() {
+        let mut game = game(UndoRule::Unlimited);
+        place(&mut game, 100.0, 100.0, 50.0);
+        place(&mut game, 400.0, 400.0, 100.0);
+        game.give_up().unwrap();
+        assert_eq!(game.current_player_idx(), Some(1));
+
+        let events = game.undo().unwrap();
+        assert_eq!(events[0], GameEvent::MoveUndone { player: 0 });
+        assert!(game.players()[0].active());
+        assert_eq!(game.current_player_idx(), Some(0));
+        game.undo().unwrap();
+        assert_eq!(game.players()[1].score(), 0.0);
+        assert_eq!(game.circles().len(), 1);
+        assert_eq!(game.current_player_idx(), Some(1));
+
+        game.redo().unwrap();
+        assert_eq!(game.players()[1].score() as u32, 31415);
+        assert_eq!(game.current_player_idx(), Some(0));
+        //A new move drops what is left to redo.
+        place(&mut game, 900.0, 300.0, 50.0);
+        assert_eq!(game.redo(), Err(GameError::NothingToRedo));
+        assert_eq!(game.history().moves().len(), 3);
+    }
+    #[test]
+    fn undo_after_game_over() {
+        let mut game = game(UndoRule::LastMove);
+        game.give_up().unwrap();
+        game.give_up().unwrap();
+        assert_eq!(game.current_player_idx(), None);
+        game.undo().unwrap();
+        assert_eq!(game.current_player_idx(), Some(1));
+        assert_eq!(game.undo(), Err(GameError::NothingToUndo));
+    }
+    #[test]
+    fn disabled_undo() {
+        let mut game = game(UndoRule::Disabled);
+        place(&mut game, 100.0, 100.0, 50.0);
+        assert_eq!(game.undo(), Err(GameError::NothingToUndo));
+        assert_eq!(game.circles().len(), 1);
+    }
+}
@@ -0,0 +1,378 @@
+//! Texts shown to the players, in a language chosen while playing.
+//!
+//! Every text is a `Message`, written out from the catalog of the current `Locale`.
+//! A catalog entry lists one template per plural form, with `{name}`-like placeholders.
+//! To add a language, add a `Locale` with its tag, plural rule and catalog.
+use crate::game::GameError;
+use crate::scoring::ScoringRule;
+use std::cell::Cell;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    English,
+    Korean,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::English, Locale::Korean];
+    /// The language tag, as in `navigator.language`.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::Korean => "ko",
+        }
+    }
+    /// The plural form `count` takes, as an index into a catalog entry.
+    fn plural(self, count: f64) -> usize {
+        match self {
+            Locale::English if count == 1.0 => 0,
+            Locale::English => 1,
+            Locale::Korean => 0,
+        }
+    }
+    /// Put between each group of three digits.
+    fn group_separator(self) -> char {
+        match self {
+            Locale::English | Locale::Korean => ',',
+        }
+    }
+    fn catalog(self, message: &Message) -> &'static [&'static str] {
+        match self {
+            Locale::English => english(message),
+            Locale::Korean => korean(message),
+        }
+    }
+}
+
+/// The `Korean` feature makes Korean the language when the browser asks for none we have.
+impl Default for Locale {
+    fn default() -> Self {
+        if cfg!(feature = "Korean") {
+            Locale::Korean
+        } else {
+            Locale::English
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+/// Reads a tag like `ko` or `en-US`, going by the language alone.
+impl FromStr for Locale {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.trim().split(['-', '_']).next().unwrap_or_default();
+        Locale::ALL
+            .iter()
+            .copied()
+            .find(|locale| locale.tag().eq_ignore_ascii_case(language))
+            .ok_or_else(|| format!("Unknown locale: {}", s))
+    }
+}
+
+thread_local! {
+    static LOCALE: Cell<Locale> = Cell::new(Locale::default());
+}
+
+/// The language texts are written in.
+pub fn locale() -> Locale {
+    LOCALE.with(Cell::get)
+}
+
+pub fn set_locale(locale: Locale) {
+    LOCALE.with(|l| l.set(locale));
+}
+
+/// The first of the languages preferred by the browser that we have.
+pub fn detect() -> Locale {
+    let navigator = web_sys::window().unwrap().navigator();
+    navigator
+        .languages()
+        .iter()
+        .filter_map(|tag| tag.as_string())
+        .chain(navigator.language())
+        .find_map(|tag| tag.parse().ok())
+        .unwrap_or_default()
+}
+
+/// `value` rounded to a whole number, with its digits grouped by thousands.
+pub fn format_number(value: f64, locale: Locale) -> String {
+    let rounded = value.round();
+    let digits = format!("{:.0}", rounded.abs());
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(locale.group_separator());
+        }
+        grouped.push(digit);
+    }
+    if rounded < 0.0 {
+        grouped.insert(0, '-');
+    }
+    grouped
+}
+
+/// Something to tell the players, written out in the current locale when displayed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// The score of a player who has given up.
+    GivenUp {
+        score: f64,
+    },
+    Winner {
+        name: String,
+    },
+    Ranking {
+        place: usize,
+        name: String,
+        score: f64,
+    },
+    UndoConsent {
+        name: String,
+    },
+    Turn {
+        name: String,
+    },
+    /// What the circle just placed is worth.
+    Placed {
+        name: String,
+        points: f64,
+    },
+    /// Why a circle was turned down.
+    Rejected(GameError),
+    GaveUp {
+        name: String,
+    },
+    Skipped {
+        name: String,
+    },
+    Timeout {
+        name: String,
+    },
+    NoRoomOut {
+        name: String,
+    },
+    NoRoomEnd,
+    Scoring(ScoringRule),
+    /// The name of a scoring rule.
+    Rule(ScoringRule),
+    Error(GameError),
+}
+
+impl Message {
+    /// The message in `locale`.
+    pub fn text(&self, locale: Locale) -> String {
+        let number = |value: f64| format_number(value, locale);
+        let (count, args): (f64, Vec<(&str, String)>) = match self {
+            Message::GivenUp { score } => (score.round(), vec![("score", number(*score))]),
+            Message::Winner { name }
+            | Message::UndoConsent { name }
+            | Message::Turn { name }
+            | Message::GaveUp { name }
+            | Message::Skipped { name }
+            | Message::Timeout { name }
+            | Message::NoRoomOut { name } => (1.0, vec![("name", name.clone())]),
+            Message::Ranking { place, name, score } => (
+                score.round(),
+                vec![
+                    ("place", place.to_string()),
+                    ("name", name.clone()),
+                    ("score", number(*score)),
+                ],
+            ),
+            Message::Placed { name, points } => (
+                points.round(),
+                vec![("name", name.clone()), ("points", number(*points))],
+            ),
+            Message::Rejected(err) => (1.0, vec![("reason", Message::Error(*err).text(locale))]),
+            Message::Scoring(rule) => (1.0, vec![("rule", Message::Rule(*rule).text(locale))]),
+            Message::NoRoomEnd | Message::Rule(_) | Message::Error(_) => (1.0, vec![]),
+        };
+        let forms = locale.catalog(self);
+        let template = forms[locale.plural(count).min(forms.len() - 1)];
+        fill(template, &args)
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text(locale()))
+    }
+}
+
+/// Replaces each `{key}` in `template` by its value in `args`.
+fn fill(template: &str, args: &[(&str, String)]) -> String {
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        let close = match rest[open..].find('}') {
+            Some(close) => open + close,
+            None => break,
+        };
+        let key = &rest[open + 1..close];
+        match args.iter().find(|(k, _)| *k == key) {
+            Some((_, value)) => text.push_str(value),
+            None => text.push_str(&rest[open..=close]),
+        }
+        rest = &rest[close + 1..];
+    }
+    text.push_str(rest);
+    text
+}
+
+fn english(message: &Message) -> &'static [&'static str] {
+    match message {
+        Message::GivenUp { .. } => &["{score} (Given Up)"],
+        Message::Winner { .. } => &["The winner is {name}!"],
+        Message::Ranking { .. } => &[
+            "{place}. {name} - {score} point",
+            "{place}. {name} - {score} points",
+        ],
+        Message::UndoConsent { .. } => {
+            &["{name} wants to take back the last move. Does everyone agree?"]
+        }
+        Message::Turn { .. } => &["{name}'s turn."],
+        Message::Placed { .. } => &[
+            "{name} scored {points} point.",
+            "{name} scored {points} points.",
+        ],
+        Message::Rejected(_) => &["Not placed: {reason}"],
+        Message::GaveUp { .. } => &["{name} gave up."],
+        Message::Skipped { .. } => &["{name} skipped a turn."],
+        Message::Timeout { .. } => &["{name} ran out of time."],
+        Message::NoRoomOut { .. } => &["No circle fits any more, so {name} is out."],
+        Message::NoRoomEnd => &["No circle fits any more. The game is over."],
+        Message::Scoring(_) => &["Scoring: {rule}"],
+        Message::Rule(rule) => match rule {
+            ScoringRule::Area => &["area"],
+            ScoringRule::Count => &["count"],
+            ScoringRule::Perimeter => &["perimeter"],
+            ScoringRule::EdgeBonus => &["edge bonus"],
+            ScoringRule::Territory => &["territory"],
+            ScoringRule::Smallest => &["smallest area"],
+        },
+        Message::Error(err) => match err {
+            GameError::Overlapped => &["Overlapped circle"],
+            GameError::OutsideBoard => &["Outside of the board"],
+            GameError::OnObstacle => &["Overlapped obstacle"],
+            GameError::TooSmall => &["Smaller than the minimum limit."],
+            GameError::TooLarge => &["Larger than the maximum limit."],
+            GameError::NoDraft => &["No circle is being drawn."],
+            GameError::Finished => &["The game is already finished."],
+            GameError::NothingToUndo => &["There is no move to undo."],
+            GameError::NothingToRedo => &["There is no move to redo."],
+            GameError::NotYourTurn => &["It is not the turn of the player."],
+        },
+    }
+}
+
+fn korean(message: &Message) -> &'static [&'static str] {
+    match message {
+        Message::GivenUp { .. } => &["{score} (포기)"],
+        Message::Winner { .. } => &["우승자는 {name}입니다!"],
+        Message::Ranking { .. } => &["{place}. {name} - {score}점"],
+        Message::UndoConsent { .. } => &["{name}님이 마지막 수를 무르려 합니다. 모두 동의하나요?"],
+        Message::Turn { .. } => &["{name}님의 차례입니다."],
+        Message::Placed { .. } => &["{name}님이 {points}점을 얻었습니다."],
+        Message::Rejected(_) => &["둘 수 없습니다: {reason}"],
+        Message::GaveUp { .. } => &["{name}님이 포기했습니다."],
+        Message::Skipped { .. } => &["{name}님이 차례를 넘겼습니다."],
+        Message::Timeout { .. } => &["{name}님의 시간이 다 되었습니다."],
+        Message::NoRoomOut { .. } => &["더 이상 그릴 곳이 없어 {name}님이 빠집니다."],
+        Message::NoRoomEnd => &["더 이상 그릴 곳이 없어 게임이 끝났습니다."],
+        Message::Scoring(_) => &["점수 방식: {rule}"],
+        Message::Rule(rule) => match rule {
+            ScoringRule::Area => &["넓이"],
+            ScoringRule::Count => &["개수"],
+            ScoringRule::Perimeter => &["둘레"],
+            ScoringRule::EdgeBonus => &["가장자리 보너스"],
+            ScoringRule::Territory => &["영역"],
+            ScoringRule::Smallest => &["가장 작은 넓이"],
+        },
+        Message::Error(err) => match err {
+            GameError::Overlapped => &["다른 원과 겹칩니다."],
+            GameError::OutsideBoard => &["판 밖으로 나갑니다."],
+            GameError::OnObstacle => &["장애물과 겹칩니다."],
+            GameError::TooSmall => &["최소 크기보다 작습니다."],
+            GameError::TooLarge => &["최대 크기보다 큽니다."],
+            GameError::NoDraft => &["그리는 중인 원이 없습니다."],
+            GameError::Finished => &["이미 끝난 게임입니다."],
+            GameError::NothingToUndo => &["무를 수가 없습니다."],
+            GameError::NothingToRedo => &["되돌릴 수가 없습니다."],
+            GameError::NotYourTurn => &["그 참가자의 차례가 아닙니다."],
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn parse_tags() {
+        assert_eq!("ko-KR".parse(), Ok(Locale::Korean));
+        assert_eq!("EN_us".parse(), Ok(Locale::English));
+        assert!("fr".parse::<Locale>().is_err());
+        for locale in Locale::ALL {
+            assert_eq!(locale.tag().parse(), Ok(locale));
+        }
+    }
+    #[test]
+    fn plurals_and_numbers() {
+        let ranking = |score: f64| Message::Ranking {
+            place: 1,
+            name: "R".to_string(),
+            score,
+        };
+        assert_eq!(ranking(1.2).text(Locale::English), "1. R - 1 point");
+        assert_eq!(
+            ranking(12345.6).text(Locale::English),
+            "1. R - 12,346 points"
+        );
+        assert_eq!(ranking(12345.6).text(Locale::Korean), "1. R - 12,346점");
+        assert_eq!(format_number(-1234567.0, Locale::English), "-1,234,567");
+        assert_eq!(format_number(999.4, Locale::English), "999");
+    }
+    #[test]
+    fn every_message_in_every_locale() {
+        let name = "{score}".to_string();
+        let messages = [
+            Message::GivenUp { score: 3.0 },
+            Message::Winner { name: name.clone() },
+            Message::UndoConsent { name: name.clone() },
+            Message::Turn { name: name.clone() },
+            Message::Placed {
+                name: name.clone(),
+                points: 1.0,
+            },
+            Message::Rejected(GameError::TooSmall),
+            Message::GaveUp { name: name.clone() },
+            Message::Skipped { name: name.clone() },
+            Message::Timeout { name: name.clone() },
+            Message::NoRoomOut { name },
+            Message::NoRoomEnd,
+            Message::Scoring(ScoringRule::EdgeBonus),
+            Message::Error(GameError::Overlapped),
+        ];
+        for locale in Locale::ALL {
+            for message in messages.iter() {
+                let text = message.text(locale);
+                //Names are put in as they are, even if they look like placeholders.
+                assert!(!text.contains('{') || text.contains("{score}"), "{}", text);
+            }
+        }
+        let scoring = Message::Scoring(ScoringRule::EdgeBonus);
+        assert_eq!(scoring.text(Locale::Korean), "점수 방식: 가장자리 보너스");
+        let rejected = Message::Rejected(GameError::Overlapped);
+        assert_eq!(
+            rejected.text(Locale::English),
+            "Not placed: Overlapped circle"
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use crate::keyboard::KeyCommand;
+use crate::session::with_session;
+use crate::shape::Position2d;
+use wasm_bindgen::convert::FromWasmAbi;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Where the canvas is on the screen, in CSS pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClientRect {
+    pub left: f64,
+    pub top: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Maps a client (CSS pixel) coordinate into a canvas
+/// of `canvas_width` x `canvas_height` pixels drawn inside `rect`.
+pub fn client_to_canvas(
+    client_x: f64,
+    client_y: f64,
+    rect: &ClientRect,
+    canvas_width: f64,
+    canvas_height: f64,
+) -> Position2d {
+    Position2d {
+        x: (client_x - rect.left) * canvas_width / rect.width,
+        y: (client_y - rect.top) * canvas_height / rect.height,
+    }
+}
+
+/// Reads the canvas placement anew, so that resizing or rotating the screen
+/// after the game started does not break the mapping.
+/// The result is in the CSS pixels of the canvas, whatever its backing store.
+fn to_canvas(canvas: &web_sys::HtmlCanvasElement, client_x: f64, client_y: f64) -> Position2d {
+    let rect = canvas.get_bounding_client_rect();
+    client_to_canvas(
+        client_x,
+        client_y,
+        &ClientRect {
+            left: rect.left(),
+            top: rect.top(),
+            width: rect.width(),
+            height: rect.height(),
+        },
+        canvas.client_width().into(),
+        canvas.client_height().into(),
+    )
+}
+
+fn listen<E>(
+    canvas: &web_sys::HtmlCanvasElement,
+    name: &str,
+    handler: impl FnMut(E) + 'static,
+) -> Result<(), JsValue>
+where
+    E: FromWasmAbi + 'static,
+{
+    let closure = Closure::wrap(Box::new(handler) as Box<dyn FnMut(E)>);
+    canvas.add_event_listener_with_callback(name, closure.as_ref().unchecked_ref())?;
+    closure.forget();
+    Ok(())
+}
+
+/// Registers the drag-to-draw listeners on the canvas, and the keys to draw with
+/// once it has focus.
+///
+/// Pointer Events are used when the browser has them, so mouse, pen and
+/// touch all share one path. Otherwise it falls back to mouse and touch events.
+pub fn install(canvas: &web_sys::HtmlCanvasElement) -> Result<(), JsValue> {
+    //Keep the browser from scrolling or zooming while drawing.
+    canvas.style().set_property("touch-action", "none")?;
+    install_keyboard(canvas)?;
+    let window = web_sys::window().unwrap();
+    if js_sys::Reflect::has(&window, &"PointerEvent".into())? {
+        install_pointer(canvas)
+    } else {
+        install_mouse(canvas)?;
+        install_touch(canvas)
+    }
+}
+
+fn install_keyboard(canvas: &web_sys::HtmlCanvasElement) -> Result<(), JsValue> {
+    //Let Tab reach the canvas.
+    canvas.set_tab_index(0);
+    //on:keydown
+    listen(canvas, "keydown", move |event: web_sys::KeyboardEvent| {
+        if event.ctrl_key() || event.meta_key() || event.alt_key() {
+            return;
+        }
+        if let Some(command) = KeyCommand::from_key(&event.key(), event.shift_key()) {
+            //Keys the game has no use for now, e.g. Tab on a bot's turn, do as usual.
+            if with_session(|session| session.key(command)).unwrap_or(false) {
+                event.prevent_default();
+            }
+        }
+    })
+}
+
+fn install_pointer(canvas: &web_sys::HtmlCanvasElement) -> Result<(), JsValue> {
+    //on:pointerdown
+    {
+        let target = canvas.clone();
+        listen(
+            canvas,
+            "pointerdown",
+            move |event: web_sys::PointerEvent| {
+                if !event.is_primary() {
+                    return;
+                }
+                event.prevent_default();
+                //Keep receiving the drag even if it leaves the canvas.
+                let _ = target.set_pointer_capture(event.pointer_id());
+                let pos = to_canvas(&target, event.client_x().into(), event.client_y().into());
+                with_session(|session| session.begin_circle(pos));
+            },
+        )?;
+    }
+    //on:pointermove
+    {
+        let target = canvas.clone();
+        listen(
+            canvas,
+            "pointermove",
+            move |event: web_sys::PointerEvent| {
+                if !event.is_primary() {
+                    return;
+                }
+                let pos = to_canvas(&target, event.client_x().into(), event.client_y().into());
+                with_session(|session| session.resize_circle(pos));
+            },
+        )?;
+    }
+    //on:pointerup
+    listen(canvas, "pointerup", move |event: web_sys::PointerEvent| {
+        if event.is_primary() {
+            with_session(|session| session.commit_circle());
+        }
+    })?;
+    //on:pointercancel
+    listen(
+        canvas,
+        "pointercancel",
+        move |event: web_sys::PointerEvent| {
+            if event.is_primary() {
+                with_session(|session| session.cancel_circle());
+            }
+        },
+    )
+}
+
+fn install_mouse(canvas: &web_sys::HtmlCanvasElement) -> Result<(), JsValue> {
+    //on:mousedown
+    {
+        let target = canvas.clone();
+        listen(canvas, "mousedown", move |event: web_sys::MouseEvent| {
+            let pos = to_canvas(&target, event.client_x().into(), event.client_y().into());
+            with_session(|session| session.begin_circle(pos));
+        })?;
+    }
+    //on:mousemove
+    {
+        let target = canvas.clone();
+        listen(canvas, "mousemove", move |event: web_sys::MouseEvent| {
+            let pos = to_canvas(&target, event.client_x().into(), event.client_y().into());
+            with_session(|session| session.resize_circle(pos));
+        })?;
+    }
+    //on:mouseup
+    listen(canvas, "mouseup", move |_event: web_sys::MouseEvent| {
+        with_session(|session| session.commit_circle());
+    })?;
+    //on:mouseleave
+    listen(canvas, "mouseleave", move |_event: web_sys::MouseEvent| {
+        with_session(|session| session.cancel_circle());
+    })
+}
+
+fn install_touch(canvas: &web_sys::HtmlCanvasElement) -> Result<(), JsValue> {
+    //on:touchstart
+    {
+        let target = canvas.clone();
+        listen(canvas, "touchstart", move |event: web_sys::TouchEvent| {
+            event.prevent_default();
+            if let Some(touch) = event.changed_touches().get(0) {
+                let pos = to_canvas(&target, touch.client_x().into(), touch.client_y().into());
+                with_session(|session| session.begin_circle(pos));
+            }
+        })?;
+    }
+    //on:touchmove
+    {
+        let target = canvas.clone();
+        listen(canvas, "touchmove", move |event: web_sys::TouchEvent| {
+            event.prevent_default();
+            if let Some(touch) = event.changed_touches().get(0) {
+                let pos = to_canvas(&target, touch.client_x().into(), touch.client_y().into());
+                with_session(|session| session.resize_circle(pos));
+            }
+        })?;
+    }
+    //on:touchend
+    listen(canvas, "touchend", move |event: web_sys::TouchEvent| {
+        event.prevent_default();
+        with_session(|session| session.commit_circle());
+    })?;
+    //on:touchcancel
+    listen(canvas, "touchcancel", move |_event: web_sys::TouchEvent| {
+        with_session(|session| session.cancel_circle());
+    })
+}
+
+/// Binds Ctrl+Z to undo, and Ctrl+Y or Ctrl+Shift+Z to redo.
+pub fn install_shortcuts() -> Result<(), JsValue> {
+    let closure = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+        if !(event.ctrl_key() || event.meta_key()) {
+            let typing = event
+                .target()
+                .and_then(|t| t.dyn_into::<web_sys::Element>().ok())
+                .is_some_and(|e| matches!(e.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT"));
+            if !typing && !event.alt_key() && event.key().eq_ignore_ascii_case("h") {
+                with_session(|session| session.set_hints(!session.hints_shown()));
+            }
+            return;
+        }
+        match (event.key().to_ascii_lowercase().as_str(), event.shift_key()) {
+            ("z", false) => {
+                event.prevent_default();
+                with_session(|session| session.undo());
+            }
+            ("y", _) | ("z", true) => {
+                event.prevent_default();
+                with_session(|session| session.redo());
+            }
+            _ => {}
+        }
+    }) as Box<dyn FnMut(_)>);
+    web_sys::window()
+        .unwrap()
+        .add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn client_to_canvas_scales_and_offsets() {
+        let rect = ClientRect {
+            left: 10.0,
+            top: 20.0,
+            width: 640.0,
+            height: 360.0,
+        };
+        let pos = client_to_canvas(10.0, 20.0, &rect, 1280.0, 720.0);
+        assert_eq!(pos, Position2d { x: 0.0, y: 0.0 });
+        let pos = client_to_canvas(330.0, 200.0, &rect, 1280.0, 720.0);
+        assert_eq!(pos, Position2d { x: 640.0, y: 360.0 });
+        let pos = client_to_canvas(650.0, 380.0, &rect, 1280.0, 720.0);
+        assert_eq!(
+            pos,
+            Position2d {
+                x: 1280.0,
+                y: 720.0
+            }
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! Placing circles without a pointer: a crosshair moved by the arrow keys.
+use crate::shape::{Circle, Position2d, Rect};
+use std::cell::Cell;
+
+/// How far a key press moves the crosshair, and how much it changes the radius,
+/// in board units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Steps {
+    pub distance: f64,
+    pub radius: f64,
+}
+
+impl Default for Steps {
+    fn default() -> Self {
+        Steps {
+            distance: 10.0,
+            radius: 5.0,
+        }
+    }
+}
+
+thread_local! {
+    /// Kept across games.
+    static STEPS: Cell<Steps> = Cell::new(Steps::default());
+}
+
+pub fn steps() -> Steps {
+    STEPS.with(Cell::get)
+}
+
+pub fn set_steps(steps: Steps) {
+    STEPS.with(|s| s.set(steps));
+}
+
+/// What a key does to the circle being placed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyCommand {
+    /// Moves the center by this many steps right and down.
+    Move(f64, f64),
+    /// Grows the radius by this many steps, or shrinks it if negative.
+    Grow(f64),
+    Commit,
+    Cancel,
+    /// Goes to the give-up button of the player on turn.
+    FocusGiveUp,
+}
+
+impl KeyCommand {
+    /// The command for `key`, as in `KeyboardEvent.key`. Shift turns arrows into resizing.
+    pub fn from_key(key: &str, shift: bool) -> Option<KeyCommand> {
+        let command = match (key, shift) {
+            ("ArrowLeft", false) => KeyCommand::Move(-1.0, 0.0),
+            ("ArrowRight", false) => KeyCommand::Move(1.0, 0.0),
+            ("ArrowUp", false) => KeyCommand::Move(0.0, -1.0),
+            ("ArrowDown", false) => KeyCommand::Move(0.0, 1.0),
+            ("ArrowUp", true) | ("ArrowRight", true) | ("+", _) | ("=", _) => KeyCommand::Grow(1.0),
+            ("ArrowDown", true) | ("ArrowLeft", true) | ("-", _) | ("_", _) => {
+                KeyCommand::Grow(-1.0)
+            }
+            ("Enter", _) => KeyCommand::Commit,
+            ("Escape", _) => KeyCommand::Cancel,
+            ("Tab", _) => KeyCommand::FocusGiveUp,
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+/// Where the next circle goes, and how large it is so far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crosshair {
+    center: Position2d,
+    radius: f64,
+}
+
+impl Crosshair {
+    pub fn new(center: Position2d) -> Crosshair {
+        Crosshair {
+            center,
+            radius: 0.0,
+        }
+    }
+    pub fn center(&self) -> Position2d {
+        self.center
+    }
+    /// Moves or resizes by `command`, keeping the center within `bounds`.
+    /// Other commands are left to the caller.
+    pub fn apply(&mut self, command: KeyCommand, steps: Steps, bounds: &Rect) {
+        match command {
+            KeyCommand::Move(dx, dy) => {
+                let origin = bounds.position();
+                self.center = Position2d {
+                    x: (self.center.x + dx * steps.distance)
+                        .max(origin.x)
+                        .min(origin.x + bounds.width()),
+                    y: (self.center.y + dy * steps.distance)
+                        .max(origin.y)
+                        .min(origin.y + bounds.height()),
+                };
+            }
+            KeyCommand::Grow(by) => self.radius = (self.radius + by * steps.radius).max(0.0),
+            KeyCommand::Commit | KeyCommand::Cancel | KeyCommand::FocusGiveUp => {}
+        }
+    }
+    /// Starts over at the same place with no circle.
+    pub fn reset(&mut self) {
+        self.radius = 0.0;
+    }
+    /// The circle being placed, once it has grown at all.
+    pub fn circle(&self) -> Option<Circle> {
+        (self.radius > 0.0).then(|| Circle::new(self.center.x, self.center.y, self.radius))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn keys() {
+        assert_eq!(
+            KeyCommand::from_key("ArrowLeft", false),
+            Some(KeyCommand::Move(-1.0, 0.0))
+        );
+        assert_eq!(
+            KeyCommand::from_key("ArrowUp", true),
+            Some(KeyCommand::Grow(1.0))
+        );
+        assert_eq!(
+            KeyCommand::from_key("-", false),
+            Some(KeyCommand::Grow(-1.0))
+        );
+        assert_eq!(
+            KeyCommand::from_key("Enter", false),
+            Some(KeyCommand::Commit)
+        );
+        assert_eq!(KeyCommand::from_key("a", false), None);
+    }
+    #[test]
+    fn crosshair_stays_on_the_board() {
+        let bounds = Rect::new(0.0, 0.0, 100.0, 50.0);
+        let steps = Steps {
+            distance: 30.0,
+            radius: 4.0,
+        };
+        let mut crosshair = Crosshair::new(Position2d { x: 50.0, y: 25.0 });
+        crosshair.apply(KeyCommand::Move(1.0, 0.0), steps, &bounds);
+        crosshair.apply(KeyCommand::Move(1.0, 1.0), steps, &bounds);
+        assert_eq!(crosshair.center(), Position2d { x: 100.0, y: 50.0 });
+        assert!(crosshair.circle().is_none());
+        crosshair.apply(KeyCommand::Grow(1.0), steps, &bounds);
+        crosshair.apply(KeyCommand::Grow(1.0), steps, &bounds);
+        assert_eq!(crosshair.circle().map(|c| c.radius()), Some(8.0));
+        crosshair.apply(KeyCommand::Grow(-3.0), steps, &bounds);
+        assert!(crosshair.circle().is_none());
+    }
+}
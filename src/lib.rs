@@ -1,12 +1,42 @@
-mod color;
-mod shape;
+mod announce;
+pub mod board;
+pub mod bot;
+pub mod clock;
+pub mod color;
+pub mod game;
+pub mod history;
+pub mod i18n;
+mod input;
+mod keyboard;
+pub mod map;
+pub mod notation;
+pub mod palette;
+mod render;
+pub mod renderer;
+pub mod replay;
+pub mod save;
+pub mod scoring;
+mod session;
+pub mod setup;
+pub mod shape;
+pub mod theme;
 mod utils;
-#[macro_use]
-mod text;
+pub mod viewport;
+use board::Board;
 use color::Color;
-use shape::{Circle, Position2d, Rect};
-use std::cell::{Cell, RefCell};
-use std::rc::Rc;
+use game::{Game, PlacedCircle};
+use i18n::{Locale, Message};
+use keyboard::Crosshair;
+use map::Map;
+use palette::{Palette, Pattern};
+use renderer::{CanvasRenderer, Renderer, SvgRenderer};
+use replay::{Entry, Record};
+use session::{with_session, Session};
+pub use setup::GameSetup;
+use shape::{Circle, Rect};
+use std::cell::RefCell;
+use theme::Theme;
+use viewport::Viewport;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
@@ -15,136 +45,308 @@ use wasm_bindgen::JsCast;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-impl From<Color> for JsValue {
-    fn from(s: Color) -> JsValue {
-        s.to_string().into()
-    }
-}
-
 trait Drawable {
-    fn draw(&self, context: &web_sys::CanvasRenderingContext2d, fill_style: &JsValue) -> ();
+    fn draw(&self, renderer: &mut dyn Renderer, fill_style: &str);
 }
 
 impl Drawable for Circle {
-    fn draw(&self, context: &web_sys::CanvasRenderingContext2d, fill_style: &JsValue) -> () {
-        context.begin_path();
+    fn draw(&self, renderer: &mut dyn Renderer, fill_style: &str) {
+        renderer.begin_path();
         let pos = self.position();
-        context
-            .arc(
-                pos.x.round(),
-                pos.y.round(),
-                self.radius().round(),
-                0.0,
-                std::f64::consts::PI * 2.0,
-            )
-            .unwrap();
-        context.set_fill_style(fill_style);
-        context.fill();
-        context.close_path();
+        renderer.arc(pos.x, pos.y, self.radius(), 0.0, std::f64::consts::PI * 2.0);
+        renderer.fill(fill_style);
     }
 }
-#[derive(Debug, Clone)]
-struct Player {
-    name: &'static str,
-    color: Color,
-    button: web_sys::HtmlButtonElement,
-    active: bool,
-    score: u32,
-}
-
-impl Player {
-    fn new(name: &'static str, color: Color, button: web_sys::HtmlButtonElement) -> Player {
-        Player {
-            name,
-            color,
-            button,
-            active: true,
-            score: 0,
+
+/// What the board layer is drawn from.
+#[derive(PartialEq)]
+struct BoardKey {
+    map: Map,
+    circles: Vec<PlacedCircle>,
+    colors: Vec<Color>,
+    palette: Palette,
+    patterns: bool,
+    theme: Theme,
+    viewport: Viewport,
+}
+
+impl BoardKey {
+    fn of(game: &Game, viewport: &Viewport) -> BoardKey {
+        BoardKey {
+            map: game.map().clone(),
+            circles: game.circles().to_vec(),
+            colors: game.players().iter().map(|p| p.color()).collect(),
+            palette: palette::palette(),
+            patterns: palette::patterns_shown(),
+            theme: theme::theme(),
+            viewport: *viewport,
         }
     }
-    fn deactivate(&mut self) {
-        self.active = false;
-        self.set_text(&format!(TEMPLATE_TEXT_GIVEN_UP!(), self.score));
-        self.set_disabled(true);
-    }
-    fn color(&self) -> &Color {
-        &self.color
-    }
-    fn set_text(&mut self, msg: &str) {
-        self.button
-            .set_inner_text(&format!("{}: {}", self.name, msg));
-    }
-    fn set_disabled(&mut self, disabled: bool) {
-        self.button.set_disabled(disabled);
-    }
-    fn active(&self) -> bool {
-        self.active
+}
+
+/// Paints the board, its obstacles and the circles placed on it.
+fn draw_board(
+    context: &web_sys::CanvasRenderingContext2d,
+    game: &Game,
+    theme: &Theme,
+    viewport: &Viewport,
+) {
+    viewport.prepare(context, game.board(), &theme.letterbox.to_string());
+    let renderer = &mut CanvasRenderer::new(context);
+    draw_surface(renderer, game, theme, viewport.scale());
+    draw_pieces(renderer, game, theme, viewport.scale());
+}
+
+/// Paints the play area and its grid, with lines `scale` times thinner.
+fn draw_surface(renderer: &mut dyn Renderer, game: &Game, theme: &Theme, scale: f64) {
+    let bounds = game.board().bounds();
+    renderer.save();
+    renderer.begin_path();
+    game.board().trace(renderer);
+    renderer.clip();
+    renderer.fill_rect(&bounds, &theme.background.to_string());
+    if let Some(grid) = theme.grid {
+        let origin = bounds.position();
+        let (right, bottom) = (origin.x + bounds.width(), origin.y + bounds.height());
+        renderer.begin_path();
+        let mut x = origin.x + theme.grid_spacing;
+        while x < right {
+            renderer.move_to(x, origin.y);
+            renderer.line_to(x, bottom);
+            x += theme.grid_spacing;
+        }
+        let mut y = origin.y + theme.grid_spacing;
+        while y < bottom {
+            renderer.move_to(origin.x, y);
+            renderer.line_to(right, y);
+            y += theme.grid_spacing;
+        }
+        renderer.stroke(&grid.to_string(), 1.0 / scale, &[]);
     }
-    fn score(&self) -> u32 {
-        self.score
+    renderer.restore();
+}
+
+/// Paints the obstacles, the placed circles and the edge of the board,
+/// with lines `scale` times thinner.
+fn draw_pieces(renderer: &mut dyn Renderer, game: &Game, theme: &Theme, scale: f64) {
+    let outline = theme.outline.to_string();
+    for obstacle in game.map().obstacles() {
+        renderer.begin_path();
+        obstacle.trace(renderer);
+        renderer.fill(&theme.obstacle.to_string());
+        renderer.stroke(&outline, 2.0 / scale, &[]);
     }
-    fn add_score(&mut self, amount: u32) -> u32 {
-        self.score += amount;
-        self.score
+    for placed in game.circles() {
+        draw_owned(
+            renderer,
+            &placed.circle,
+            placed.owner,
+            game.players()[placed.owner].color(),
+        );
     }
+    renderer.begin_path();
+    game.board().trace(renderer);
+    renderer.stroke(&outline, 2.0 / scale, &[]);
 }
 
-fn validate(
-    board: &Rect,
-    circles: &[Circle],
-    current_circle: &Circle,
-    min_radius: f64,
-    max_radius: f64,
-) -> Result<(), &'static str> {
-    let radius = current_circle.radius();
-    if circles.iter().any(|c| c.is_overlapped(current_circle)) {
-        Err("Overlapped circle")
-    } else if board.is_outside(current_circle) {
-        Err("Outside of the board")
-    } else if min_radius > radius {
-        Err("Smaller than the minimum limit.")
-    } else if radius > max_radius {
-        Err("Larger than the maximum limit.")
-    } else {
-        Ok(())
+/// Paints `circle` in `color`, with the pattern of the `owner`th player if patterns are on.
+fn draw_owned(renderer: &mut dyn Renderer, circle: &Circle, owner: usize, color: Color) {
+    let palette = palette::palette();
+    circle.draw(renderer, &palette.paint(color).to_string());
+    if palette::patterns_shown() {
+        Pattern::of_seat(owner).draw(circle, &palette.ink(color).to_string(), renderer);
     }
 }
-/// Returns:\
-/// `Some(usize)` Index of the next active player. (include themselves.)
-/// `None` if none of the players active.
-fn next_player_idx(players: &[Player], current_idx: Option<usize>) -> Option<usize> {
-    if let Some(idx) = current_idx {
-        let number_of_players = players.len();
-        for i in 0..number_of_players {
-            let next_idx = (idx + 1 + i) % number_of_players;
-            if players[next_idx].active() {
-                return Some(next_idx);
+
+/// Paints `circle`, which does not fit, pale and with a dashed edge
+/// so that it stands out whatever the colors. The edge is `scale` times thinner.
+fn draw_rejected(renderer: &mut dyn Renderer, circle: &Circle, color: Color, scale: f64) {
+    let paint = palette::palette().paint(color);
+    circle.draw(renderer, &paint.to_light().to_string());
+    let dash = [6.0 / scale, 4.0 / scale];
+    renderer.stroke(&paint.to_string(), 2.0 / scale, &dash);
+}
+
+/// Paints the circle being drawn and, once finished, the ranking, over the board.
+/// Lines are `scale` times thinner.
+fn draw_game(renderer: &mut dyn Renderer, game: &Game, theme: &Theme, scale: f64) {
+    let board = game.board().bounds();
+    let (width, height) = (board.width(), board.height());
+    let origin = board.position();
+    if let (Some(idx), Some(player)) = (game.current_player_idx(), game.current_player()) {
+        if let Some((circle, result)) = game.draft() {
+            if result.is_ok() {
+                draw_owned(renderer, circle, idx, player.color());
+            } else {
+                draw_rejected(renderer, circle, player.color(), scale);
             }
         }
+    } else {
+        //No active player means the game is finished.
+        if let Some(backdrop) = theme.ranking_backdrop {
+            renderer.begin_path();
+            game.board().trace(renderer);
+            renderer.fill(&backdrop.to_string());
+        }
+        let center: f64 = origin.x + width / 2.0f64;
+        let line_space: f64 = 50.0;
+        let middle: f64 =
+            origin.y + (height - line_space * (game.players().len() + 1) as f64) / 2.0;
+        let font = &theme.font(50.0);
+        let outline = &theme.ranking_outline.to_string();
+        for (i, &idx) in game.ranking().iter().enumerate() {
+            let player = &game.players()[idx];
+            let color = palette::palette().paint(player.color()).to_string();
+            if i == 0 {
+                let msg = &Message::Winner {
+                    name: player.name().to_string(),
+                }
+                .to_string();
+                renderer.stroke_text(msg, center, middle, font, outline, 4.0);
+                renderer.fill_text(msg, center, middle, font, &color);
+            }
+            let msg = &Message::Ranking {
+                place: i + 1,
+                name: player.name().to_string(),
+                score: player.score(),
+            }
+            .to_string();
+            let y = middle + line_space * (i as f64 + 1.0);
+            renderer.stroke_text(msg, center, y, font, outline, 4.0);
+            renderer.fill_text(msg, center, y, font, &color);
+        }
+        let msg = &Message::Scoring(game.scoring_rule()).to_string();
+        let below = middle + line_space * (game.players().len() as f64 + 1.0);
+        let text = theme.ranking_text.to_string();
+        renderer.fill_text(msg, center, below, &theme.font(30.0), &text);
     }
-    None
 }
 
-fn disable_other_players(players: &mut [Player], current_idx: Option<usize>) {
-    for player in players.iter_mut() {
-        player.set_disabled(true); //Enable the 1st player only.
-    }
-    if let Some(idx) = current_idx {
-        players[idx].set_disabled(false);
+/// Paints the whole board in the current theme, e.g. to share the result.
+fn draw_picture(renderer: &mut dyn Renderer, game: &Game) {
+    let theme = theme::theme();
+    draw_surface(renderer, game, &theme, 1.0);
+    draw_pieces(renderer, game, &theme, 1.0);
+    draw_game(renderer, game, &theme, 1.0);
+}
+
+/// Outlines the `spots` in dashes, each with the points it would be worth.
+/// Lines and text are `scale` times smaller, to look the same at any zoom.
+fn draw_hints(
+    renderer: &mut dyn Renderer,
+    spots: &[Circle],
+    game: &Game,
+    theme: &Theme,
+    scale: f64,
+) {
+    let color = match highlight(game, theme) {
+        Some(color) => color,
+        None => return,
+    };
+    let dash = 8.0 / scale;
+    let font = theme.font(20.0 / scale);
+    let points = |spot: &Circle| game.scoring_rule().points(game.map(), spot);
+    for spot in spots {
+        let pos = spot.position();
+        renderer.begin_path();
+        renderer.arc(pos.x, pos.y, spot.radius(), 0.0, std::f64::consts::PI * 2.0);
+        renderer.stroke(&color, 2.0 / scale, &[dash, dash]);
+        let label = i18n::format_number(points(spot), i18n::locale());
+        renderer.fill_text(&label, pos.x, pos.y, &font, &color);
     }
 }
 
+/// The color of the marks for the player on turn, if any.
+fn highlight(game: &Game, theme: &Theme) -> Option<String> {
+    let player = game.current_player()?;
+    let paint = palette::palette().paint(player.color());
+    Some(theme.highlight.apply(paint).to_string())
+}
+
+/// Marks where the keyboard would place the next circle.
+fn draw_crosshair(
+    renderer: &mut dyn Renderer,
+    crosshair: &Crosshair,
+    game: &Game,
+    theme: &Theme,
+    scale: f64,
+) {
+    let color = match highlight(game, theme) {
+        Some(color) => color,
+        None => return,
+    };
+    let center = crosshair.center();
+    let arm = 12.0 / scale;
+    renderer.begin_path();
+    renderer.move_to(center.x - arm, center.y);
+    renderer.line_to(center.x + arm, center.y);
+    renderer.move_to(center.x, center.y - arm);
+    renderer.line_to(center.x, center.y + arm);
+    renderer.stroke(&color, 2.0 / scale, &[]);
+}
+
+/// Shows `text` on a band across the top of `board`.
+fn draw_notice(renderer: &mut dyn Renderer, text: &str, board: &Rect, theme: &Theme) {
+    let origin = board.position();
+    let height = 60.0;
+    let band = Rect::new(origin.x, origin.y, board.width(), height);
+    renderer.fill_rect(&band, &theme.notice.to_string());
+    let (x, y) = (origin.x + board.width() / 2.0, origin.y + height / 2.0);
+    let font = theme.font(28.0);
+    renderer.fill_text(text, x, y, &font, &theme.notice_text.to_string());
+}
+
 #[wasm_bindgen]
 extern "C" {
-    fn setInterval(closure: &Closure<dyn FnMut()>, millis: u32) -> f64;
-    fn cancelInterval(token: f64);
-
     #[wasm_bindgen(js_namespace = console)]
     fn log(msg: &str);
     #[wasm_bindgen(js_namespace = console, js_name = log)]
     fn stylish_log(msg: &str, style: &str);
 }
 
+thread_local! {
+    static ANIMATION: RefCell<Option<render::Animation>> = const { RefCell::new(None) };
+}
+
+/// Paints everything on the screen: the board from its layer, then what changes more often.
+fn draw_frame(
+    context: &web_sys::CanvasRenderingContext2d,
+    layer: &mut render::Layer<BoardKey>,
+    session: &mut Session,
+) {
+    let hints = session.hints().to_vec();
+    let game = session.shown_game();
+    let viewport = session.viewport();
+    let key = BoardKey::of(game, viewport);
+    let theme = key.theme.clone();
+    layer.paint(context, key, |layer| {
+        draw_board(layer, game, &theme, viewport)
+    });
+    viewport.apply(context);
+    let renderer = &mut CanvasRenderer::new(context);
+    draw_game(renderer, game, &theme, viewport.scale());
+    draw_hints(renderer, &hints, game, &theme, viewport.scale());
+    if let Some(crosshair) = session.crosshair() {
+        draw_crosshair(renderer, crosshair, game, &theme, viewport.scale());
+    }
+    //Show the attempt that was just turned down while replaying.
+    if let Some(Record {
+        entry: Entry::Rejected { player, circle, .. },
+        ..
+    }) = session.replay().and_then(|replay| replay.last_record())
+    {
+        draw_rejected(
+            renderer,
+            circle,
+            game.players()[*player].color(),
+            viewport.scale(),
+        );
+    }
+    if let Some(message) = session.notice() {
+        draw_notice(renderer, &message.to_string(), viewport.board(), &theme);
+    }
+}
+
 #[wasm_bindgen(start)]
 pub fn start() -> Result<(), JsValue> {
     utils::set_panic_hook();
@@ -159,261 +361,328 @@ pub fn start() -> Result<(), JsValue> {
         .get_context("2d")?
         .unwrap()
         .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
-    let context = Rc::new(context);
-    //Iint Players
-    let mut players = Vec::with_capacity(4);
-    let red_button = document
-        .get_element_by_id("player_red")
-        .expect("Need an canvase element with id=\"player_red\".")
-        .dyn_into::<web_sys::HtmlButtonElement>()?;
-    players.push(Player::new("R", Color::Red, red_button));
-    let green_button = document
-        .get_element_by_id("player_green")
-        .expect("Need an canvase element with id=\"player_green\".")
-        .dyn_into::<web_sys::HtmlButtonElement>()?;
-    players.push(Player::new("G", Color::Green, green_button));
-    let blue_button = document
-        .get_element_by_id("player_blue")
-        .expect("Need an canvase element with id=\"player_blue\".")
-        .dyn_into::<web_sys::HtmlButtonElement>()?;
-    players.push(Player::new("B", Color::Blue, blue_button));
-    let yellow_button = document
-        .get_element_by_id("player_yellow")
-        .expect("Need an canvase element with id=\"player_yellow\".")
-        .dyn_into::<web_sys::HtmlButtonElement>()?;
-    players.push(Player::new("Y", Color::Yellow, yellow_button));
-    disable_other_players(&mut players, Some(0));
-    //Global game contexts
-    let players = Rc::new(RefCell::new(players));
-    let current_player_idx = Rc::new(Cell::new(Some(0)));
-    let circles = Rc::new(RefCell::new(Vec::<Circle>::new()));
-    let circle_fill_styles = Rc::new(RefCell::new(Vec::<Color>::new()));
-    let current_circle = Rc::new(RefCell::new(Circle::new(0.0, 0.0, 0.0)));
-    let current_circle_valid = Rc::new(Cell::new(true));
-    let width = 1280;
-    let height = 720;
-    canvas.set_width(width);
-    canvas.set_height(height);
-    let client_to_canvas_width: f64 = width as f64 / canvas.client_width() as f64;
-    let client_to_canvas_height: f64 = height as f64 / canvas.client_height() as f64;
-    log(&format! {"w {}h {}",client_to_canvas_width,client_to_canvas_height});
-    let (min_radius, max_radius) = (18.0, 360.0);
-    let board = Rect::new(0.0, 0.0, width.into(), height.into());
-    let pressed = Rc::new(Cell::new(false));
-
-    //on:mousedown
-    {
-        let pressed = pressed.clone();
-        let circle = current_circle.clone();
-        let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
-            circle.borrow_mut().set_position(
-                event.offset_x() as f64 * client_to_canvas_width,
-                event.offset_y() as f64 * client_to_canvas_height,
-            );
-            pressed.set(true);
-        }) as Box<dyn FnMut(_)>);
-        canvas.add_event_listener_with_callback("mousedown", closure.as_ref().unchecked_ref())?;
-        closure.forget();
-    }
-    //on:mousemove
-    {
-        let pressed = pressed.clone();
-        let circles = circles.clone();
-        let circle = current_circle.clone();
-        let current_circle_valid = current_circle_valid.clone();
-        let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
-            if pressed.get() {
-                let center = circle.borrow().position();
-                {
-                    circle.borrow_mut().set_radius(
-                        center
-                            .distance(&Position2d {
-                                x: event.offset_x() as f64 * client_to_canvas_width,
-                                y: event.offset_y() as f64 * client_to_canvas_height,
-                            })
-                            .floor(),
-                    );
-                }
-                let valid = match validate(
-                    &board,
-                    &circles.borrow(),
-                    &circle.borrow(),
-                    min_radius,
-                    max_radius,
-                ) {
-                    Ok(_) => true,
-                    Err(msg) => {
-                        log(msg);
-                        false
-                    }
-                };
-                current_circle_valid.set(valid);
+    use_locale(i18n::detect())?;
+    watch_color_scheme()?;
+    viewport::install(&canvas)?;
+    input::install(&canvas)?;
+    input::install_shortcuts()?;
+    //Draw loop
+    let mut layer = render::Layer::new(&document)?;
+    let animation = render::Animation::start(move |elapsed| {
+        with_session(|session| {
+            session.tick(elapsed);
+            if session.take_dirty() {
+                draw_frame(&context, &mut layer, session);
             }
-        }) as Box<dyn FnMut(_)>);
-        canvas.add_event_listener_with_callback("mousemove", closure.as_ref().unchecked_ref())?;
-        closure.forget();
+        });
+    })?;
+    ANIMATION.with(|a| *a.borrow_mut() = Some(animation));
+
+    let shared = match notation::take_from_url() {
+        Some(Ok(game)) => Some(game),
+        Some(Err(err)) => {
+            log(&err.to_string());
+            None
+        }
+        None => None,
+    };
+    let game = match shared.or_else(save::load_autosave) {
+        Some(game) => game,
+        None => GameSetup::default().build()?,
+    };
+    session::start(game)
+}
+
+/// Stops drawing and leaves the game, e.g. before taking the canvas off the page.
+#[wasm_bindgen]
+pub fn stop() {
+    if let Some(animation) = ANIMATION.with(|a| a.borrow_mut().take()) {
+        animation.cancel();
     }
-    //on:mouseup
-    {
-        let pressed = pressed.clone();
-        let players = players.clone();
-        let current_player_idx = current_player_idx.clone();
-        let circles = circles.clone();
-        let circle_fill_styles = circle_fill_styles.clone();
-        let current_circle = current_circle.clone();
-        let current_circle_valid = current_circle_valid.clone();
-        let closure = Closure::wrap(Box::new(move |_event: web_sys::MouseEvent| {
-            let valid = match validate(
-                &board,
-                &circles.borrow(),
-                &current_circle.borrow(),
-                min_radius,
-                max_radius,
-            ) {
-                Ok(_) => true,
-                Err(msg) => {
-                    log(msg);
-                    false
-                }
-            };
-            current_circle_valid.set(valid);
-            if valid {
-                let area = current_circle.borrow().area();
-                log(&format!("{}", area));
-                {
-                    circles.borrow_mut().push(current_circle.take());
-                }
-                let current_player_idx_copy = current_player_idx.get();
-                if let Some(idx) = current_player_idx_copy {
-                    let current_player_color = *players.borrow()[idx].color();
-                    {
-                        let score = players.borrow_mut()[idx].add_score(area as u32);
-                        {
-                            players.borrow_mut()[idx].set_text(&format!("{}", score));
-                        }
-                        stylish_log(
-                            &format!("Player %c ★ {}", score),
-                            &format!("color:{};", current_player_color),
-                        );
-                    }
-                    {
-                        circle_fill_styles.borrow_mut().push(current_player_color);
-                    }
-                }
-                let next_player_idx = next_player_idx(&players.borrow(), current_player_idx_copy);
-                current_player_idx.set(next_player_idx);
-                disable_other_players(&mut players.borrow_mut(), next_player_idx);
-            } else {
-                let circle = current_circle.take();
-                log(&format!("{:?} is invalid", circle));
-            }
-            pressed.set(false);
-        }) as Box<dyn FnMut(_)>);
-        canvas.add_event_listener_with_callback("mouseup", closure.as_ref().unchecked_ref())?;
-        closure.forget();
+    session::stop();
+}
+
+/// Starts a new game with the given players, replacing the current one.
+#[wasm_bindgen]
+pub fn start_game(setup: &GameSetup) -> Result<(), JsValue> {
+    session::start(setup.build()?)
+}
+
+/// The names of the built-in maps, for `GameSetup.use_map`.
+#[wasm_bindgen]
+pub fn map_names() -> Vec<String> {
+    map::Map::preset_names().map(String::from).collect()
+}
+
+/// The current game as JSON, to be read back by `import_game`.
+#[wasm_bindgen]
+pub fn export_game() -> Option<String> {
+    with_session(|session| save::Snapshot::of(session.game()).to_json())
+}
+
+/// Saves the current game as a JSON file.
+#[wasm_bindgen]
+pub fn download_game() -> Result<(), JsValue> {
+    match export_game() {
+        Some(json) => save::download("game-of-compass.json", &json),
+        None => Ok(()),
     }
-    //on:mouseleave
-    {
-        let pressed = pressed.clone();
-        let circle = current_circle.clone();
-        let closure = Closure::wrap(Box::new(move |_event: web_sys::MouseEvent| {
-            log(&format!("Mouse leaved while drawing {:?}", circle.take()));
-            pressed.set(false);
-        }) as Box<dyn FnMut(_)>);
-        canvas.add_event_listener_with_callback("mouseleave", closure.as_ref().unchecked_ref())?;
-        closure.forget();
+}
+
+/// A picture of the board shown, e.g. the finished game, as an SVG document.
+#[wasm_bindgen]
+pub fn export_svg() -> Option<String> {
+    with_session(|session| {
+        let game = session.shown_game();
+        let mut svg = SvgRenderer::new(game.board().bounds());
+        draw_picture(&mut svg, game);
+        svg.finish()
+    })
+}
+
+/// Saves the picture of `export_svg` as an SVG file.
+#[wasm_bindgen]
+pub fn download_svg() -> Result<(), JsValue> {
+    match export_svg() {
+        Some(svg) => save::download_text("game-of-compass.svg", &svg, "image/svg+xml"),
+        None => Ok(()),
     }
-    //Buttons on:click
-    {
-        let players = players.clone();
-        for player in players.borrow_mut().iter_mut() {
-            let players = players.clone();
-            let current_player_idx = current_player_idx.clone();
-            let closure = Closure::wrap(Box::new(move |_event: web_sys::MouseEvent| {
-                let current_idx = current_player_idx.get();
-                if let Some(idx) = current_idx {
-                    players.borrow_mut()[idx].deactivate();
-                }
-                let next_player_idx = next_player_idx(&players.borrow(), current_idx);
-                if next_player_idx.is_none() {
-                    //GameEnd;
-                    players.borrow_mut().sort_by_key(|p| p.score());
-                }
-                current_player_idx.set(next_player_idx);
-                disable_other_players(&mut players.borrow_mut(), next_player_idx)
-            }) as Box<dyn FnMut(_)>);
-            player
-                .button
-                .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())?;
-            closure.forget();
-        }
+}
+
+/// Saves the picture of `export_svg` as a PNG file, one pixel per board unit.
+#[wasm_bindgen]
+pub fn download_png() -> Result<(), JsValue> {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<web_sys::HtmlCanvasElement>()?;
+    let context = canvas
+        .get_context("2d")?
+        .unwrap()
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
+    let drawn = with_session(|session| {
+        let game = session.shown_game();
+        let bounds = game.board().bounds();
+        canvas.set_width(bounds.width().ceil() as u32);
+        canvas.set_height(bounds.height().ceil() as u32);
+        let origin = bounds.position();
+        context.translate(-origin.x, -origin.y)?;
+        draw_picture(&mut CanvasRenderer::new(&context), game);
+        Ok::<(), JsValue>(())
+    });
+    match drawn {
+        Some(result) => result?,
+        None => return Ok(()),
     }
-    //Draw loop
-    {
-        let players = players.clone();
-        let current_player_idx = current_player_idx.clone();
-        let circle_fill_styles = circle_fill_styles.clone();
-        let circles = circles.clone();
-        let current_circle = current_circle.clone();
-        let current_circle_valid = current_circle_valid.clone();
-        let context = context.clone();
-        let closure = Closure::wrap(Box::new(move || {
-            context.clear_rect(0.0, 0.0, width.into(), height.into());
-            for (c, fill_style) in circles
-                .borrow()
-                .iter()
-                .zip(circle_fill_styles.borrow().iter())
-            {
-                c.draw(&context, &(*fill_style).into());
-            }
-            if let Some(idx) = current_player_idx.get() {
-                let current_player_color = *players.borrow()[idx].color();
-                {
-                    let fill_style = if current_circle_valid.get() {
-                        current_player_color.to_dark()
-                    } else {
-                        current_player_color.to_light()
-                    };
-                    current_circle.borrow().draw(&context, &fill_style.into());
-                }
-            } else {
-                //No active player means the game is finished.
-                let center: f64 = width as f64 / 2.0f64;
-                let line_space: f64 = 50.0;
-                let middle: f64 =
-                    (height as f64 - line_space * (players.borrow().len() + 1) as f64) / 2.0;
-                context.set_font("50px system-ui");
-                context.set_stroke_style(&"#000000".to_string().into());
-                context.set_line_width(4.0);
-                for (i, player) in players.borrow().iter().rev().enumerate() {
-                    context.set_fill_style(&(*player.color()).into());
-                    if i == 0 {
-                        let msg = &format!(TEMPLATE_TEXT_WINNER!(), player.name);
-                        context
-                            .stroke_text(msg, center, middle)
-                            .expect("Failed to stroke text.");
-                        context
-                            .fill_text(msg, center, middle)
-                            .expect("Failed to print text.");
-                    }
-                    let msg = &format!(TEMPLATE_TEXT_RANKING!(), i + 1, player.name, player.score);
-                    context
-                        .stroke_text(msg, center, middle + line_space * (i as f64 + 1.0))
-                        .expect("Failed to stroke text.");
-                    context
-                        .fill_text(msg, center, middle + line_space * (i as f64 + 1.0))
-                        .expect("Failed to print text.");
-                }
-                context.set_text_align("center");
-                context.set_text_baseline("middle");
+    let saved = Closure::once_into_js(move |blob: Option<web_sys::Blob>| {
+        if let Some(blob) = blob {
+            if let Err(err) = save::download_blob("game-of-compass.png", &blob) {
+                log(&format!("Failed to save the picture: {:?}", err));
             }
-        }) as Box<dyn FnMut()>);
-        window.set_interval_with_callback_and_timeout_and_arguments_0(
-            closure.as_ref().unchecked_ref(),
-            20,
-        )?;
-        closure.forget();
+        }
+    });
+    canvas.to_blob(saved.unchecked_ref())
+}
+
+/// Carries on the game saved by `export_game`, replacing the current one.
+#[wasm_bindgen]
+pub fn import_game(json: &str) -> Result<(), JsValue> {
+    let game = save::Snapshot::from_json(json)?.into_game()?;
+    session::start(game)
+}
+
+/// The current game in the compass notation.
+/// `None` if it was resumed from a save, as its earlier moves are unknown.
+#[wasm_bindgen]
+pub fn export_notation() -> Option<String> {
+    with_session(|session| notation::write(session.game())).flatten()
+}
+
+/// Plays out a game written in the compass notation, replacing the current one.
+#[wasm_bindgen]
+pub fn import_notation(text: &str) -> Result<(), JsValue> {
+    session::start(notation::read(text)?)
+}
+
+/// A link to this page that opens the current game.
+#[wasm_bindgen]
+pub fn share_link() -> Option<String> {
+    with_session(|session| notation::share_link(session.game())).flatten()
+}
+
+/// Looks back at the game move by move. Playing is paused until `close_replay`.
+#[wasm_bindgen]
+pub fn open_replay() -> Result<(), JsValue> {
+    with_session(|session| session.open_replay()).unwrap_or(Ok(()))
+}
+
+#[wasm_bindgen]
+pub fn close_replay() {
+    with_session(|session| session.close_replay());
+}
+
+/// Jumps to the state after the first `step` recorded actions.
+#[wasm_bindgen]
+pub fn replay_seek(step: usize) {
+    with_session(|session| session.replay_seek(step));
+}
+
+/// Steps `delta` actions forward, or back if negative.
+#[wasm_bindgen]
+pub fn replay_step(delta: i32) {
+    with_session(|session| session.replay_step(delta));
+}
+
+/// Autoplays at `speed` times the pace of the real game.
+#[wasm_bindgen]
+pub fn replay_play(speed: f64) {
+    with_session(|session| session.replay_play(speed));
+}
+
+#[wasm_bindgen]
+pub fn replay_pause() {
+    with_session(|session| session.replay_pause());
+}
+
+/// Switches the language of the game, given a tag such as `ko` or `en-US`.
+#[wasm_bindgen]
+pub fn set_locale(tag: &str) -> Result<(), JsValue> {
+    let locale: Locale = tag.parse().map_err(|e: String| JsValue::from_str(&e))?;
+    use_locale(locale)?;
+    with_session(|session| session.refresh_all());
+    Ok(())
+}
+
+/// The tag of the language in use, e.g. `en`.
+#[wasm_bindgen]
+pub fn locale() -> String {
+    i18n::locale().tag().to_string()
+}
+
+/// Makes `locale` the language of the game and of the page.
+fn use_locale(locale: Locale) -> Result<(), JsValue> {
+    i18n::set_locale(locale);
+    let document = web_sys::window().unwrap().document().unwrap();
+    match document.document_element() {
+        Some(root) => root.set_attribute("lang", locale.tag()),
+        None => Ok(()),
+    }
+}
+
+/// Sets how far, in board units, an arrow key moves the crosshair of keyboard placement,
+/// and how much +/- or Shift+arrows change the radius.
+#[wasm_bindgen]
+pub fn set_keyboard_steps(distance: f64, radius: f64) {
+    if distance > 0.0 && radius > 0.0 {
+        keyboard::set_steps(keyboard::Steps { distance, radius });
     }
+}
+
+/// Shows the players in another set of colors, e.g. one safe for color blindness.
+#[wasm_bindgen]
+pub fn set_palette(palette: Palette) {
+    palette::set_palette(palette);
+    with_session(|session| session.refresh_all());
+}
+
+/// Marks the circles of each player with their own pattern, or stops doing so.
+#[wasm_bindgen]
+pub fn set_patterns(on: bool) {
+    palette::show_patterns(on);
+    with_session(|session| session.refresh_all());
+}
+
+/// Switches to a built-in theme: `light`, `dark`, `paper` or `chalkboard`.
+/// The theme then stays, whatever the color scheme of the system.
+#[wasm_bindgen]
+pub fn set_theme(name: &str) -> Result<(), JsValue> {
+    let theme = Theme::preset(name)
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown theme: {}", name)))?;
+    use_theme(theme);
+    Ok(())
+}
+
+/// Switches to a theme written in JSON, as `export_theme` gives.
+#[wasm_bindgen]
+pub fn load_theme(json: &str) -> Result<(), JsValue> {
+    use_theme(Theme::from_json(json)?);
+    Ok(())
+}
+
+/// The theme in use, as JSON.
+#[wasm_bindgen]
+pub fn export_theme() -> String {
+    theme::theme().to_json()
+}
 
+#[wasm_bindgen]
+pub fn theme_names() -> Vec<String> {
+    Theme::PRESETS.iter().map(|name| name.to_string()).collect()
+}
+
+/// Goes back to the light or dark theme, following `prefers-color-scheme`.
+#[wasm_bindgen]
+pub fn use_system_theme() {
+    theme::follow_system(system_is_dark());
+    with_session(|session| session.refresh_all());
+}
+
+fn use_theme(theme: Theme) {
+    theme::set_theme(theme);
+    with_session(|session| session.refresh_all());
+}
+
+fn dark_scheme() -> Option<web_sys::MediaQueryList> {
+    web_sys::window()
+        .unwrap()
+        .match_media("(prefers-color-scheme: dark)")
+        .ok()
+        .flatten()
+}
+
+fn system_is_dark() -> bool {
+    dark_scheme().is_some_and(|media| media.matches())
+}
+
+/// Picks the light or dark theme as the system does, and switches along with it
+/// until a theme is chosen.
+fn watch_color_scheme() -> Result<(), JsValue> {
+    theme::follow_system(system_is_dark());
+    let media = match dark_scheme() {
+        Some(media) => media,
+        None => return Ok(()),
+    };
+    let query = media.clone();
+    let changed = Closure::wrap(Box::new(move || {
+        if theme::follows_system() {
+            theme::follow_system(query.matches());
+            with_session(|session| session.refresh_all());
+        }
+    }) as Box<dyn FnMut()>);
+    media.add_event_listener_with_callback("change", changed.as_ref().unchecked_ref())?;
+    changed.forget();
     Ok(())
 }
+
+/// Shows or hides the largest spots left. Hints are never shown in ranked games.
+/// Returns whether they are shown now.
+#[wasm_bindgen]
+pub fn toggle_hints() -> bool {
+    with_session(|session| {
+        session.set_hints(!session.hints_shown());
+        session.hints_shown()
+    })
+    .unwrap_or(false)
+}
+
+/// Takes the latest move back, if the undo rule of the game allows it.
+#[wasm_bindgen]
+pub fn undo() {
+    with_session(|session| session.undo());
+}
+
+/// Plays the latest undone move again.
+#[wasm_bindgen]
+pub fn redo() {
+    with_session(|session| session.redo());
+}
@@ -0,0 +1,229 @@
+//! Scenario maps: a board with fixed obstacles on it.
+//!
+//! Maps are read from JSON, with the shapes written as in `board`:
+//!
+//! ```json
+//! {
+//!   "name": "pillars",
+//!   "board": "1280x720",
+//!   "obstacles": ["circle (320,180) r60", "120x120+580+300"]
+//! }
+//! ```
+use crate::board::{Board, GameBoard};
+use crate::renderer::Renderer;
+use crate::shape::{largest_empty_circle, Circle, Position2d, Rect};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The built-in maps, as names, boards and obstacles.
+const PRESETS: [(&str, &str, &[&str]); 5] = [
+    ("classic", "1280x720", &[]),
+    (
+        "pillars",
+        "1280x720",
+        &[
+            "circle (320,180) r60",
+            "circle (960,180) r60",
+            "circle (320,540) r60",
+            "circle (960,540) r60",
+        ],
+    ),
+    (
+        "crossroads",
+        "1280x720",
+        &["500x40+390+340", "40x500+620+110"],
+    ),
+    ("arena", "circle (360,360) r360", &["circle (360,360) r90"]),
+    (
+        "islands",
+        "regular 6 (400,400) r400",
+        &[
+            "polygon (400,150) (460,250) (340,250)",
+            "polygon (180,500) (280,460) (250,580)",
+            "polygon (620,500) (520,460) (550,580)",
+        ],
+    ),
+];
+
+/// A shape circles cannot overlap. Touching it is fine, as with the edge of the board.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Obstacle(GameBoard);
+
+impl Obstacle {
+    pub fn shape(&self) -> &GameBoard {
+        &self.0
+    }
+    /// How far `point` is from the obstacle: negative inside it.
+    pub fn distance(&self, point: Position2d) -> f64 {
+        -self.0.clearance(point)
+    }
+    pub fn overlaps(&self, circle: &Circle) -> bool {
+        self.distance(circle.position()) < circle.radius()
+    }
+    /// Adds the outline of the obstacle to the current path of `renderer`.
+    pub fn trace(&self, renderer: &mut dyn Renderer) {
+        self.0.trace(renderer)
+    }
+}
+
+impl fmt::Display for Obstacle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for Obstacle {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Obstacle)
+    }
+}
+
+impl From<Circle> for Obstacle {
+    fn from(circle: Circle) -> Obstacle {
+        Obstacle(GameBoard::Circle(circle))
+    }
+}
+
+impl From<Rect> for Obstacle {
+    fn from(rect: Rect) -> Obstacle {
+        Obstacle(GameBoard::Rect(rect))
+    }
+}
+
+/// The form of a map in JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct MapSpec {
+    #[serde(default)]
+    name: String,
+    board: String,
+    #[serde(default)]
+    obstacles: Vec<String>,
+}
+
+/// A board and the obstacles on it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Map {
+    name: String,
+    board: GameBoard,
+    obstacles: Vec<Obstacle>,
+}
+
+impl Map {
+    /// Fails if the obstacles leave no room for any circle.
+    pub fn new(name: &str, board: GameBoard, obstacles: Vec<Obstacle>) -> Result<Map, String> {
+        let map = Map {
+            name: name.to_string(),
+            board,
+            obstacles,
+        };
+        if largest_empty_circle(&map, &[], f64::INFINITY).is_none() {
+            return Err("The obstacles leave no room on the board.".to_string());
+        }
+        Ok(map)
+    }
+    /// One of the built-in maps.
+    pub fn preset(name: &str) -> Option<Map> {
+        let (name, board, obstacles) = PRESETS.iter().find(|(n, _, _)| *n == name)?;
+        let obstacles = obstacles.iter().map(|o| o.parse().unwrap()).collect();
+        Some(Map::new(name, board.parse().unwrap(), obstacles).unwrap())
+    }
+    pub fn preset_names() -> impl Iterator<Item = &'static str> {
+        PRESETS.iter().map(|(name, _, _)| *name)
+    }
+    pub fn from_json(json: &str) -> Result<Map, String> {
+        let spec: MapSpec = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let obstacles = spec
+            .obstacles
+            .iter()
+            .map(|o| o.parse())
+            .collect::<Result<Vec<_>, _>>()?;
+        Map::new(&spec.name, spec.board.parse()?, obstacles)
+    }
+    pub fn to_json(&self) -> String {
+        let spec = MapSpec {
+            name: self.name.clone(),
+            board: self.board.to_string(),
+            obstacles: self.obstacles.iter().map(|o| o.to_string()).collect(),
+        };
+        serde_json::to_string(&spec).expect("A map is always serializable.")
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn board(&self) -> &GameBoard {
+        &self.board
+    }
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+}
+
+impl From<GameBoard> for Map {
+    fn from(board: GameBoard) -> Map {
+        Map {
+            name: String::new(),
+            board,
+            obstacles: Vec::new(),
+        }
+    }
+}
+
+impl From<Rect> for Map {
+    fn from(rect: Rect) -> Map {
+        Map::from(GameBoard::Rect(rect))
+    }
+}
+
+/// The room left by the board and its obstacles together.
+/// Its outline is the one of the board; obstacles are drawn on their own.
+impl Board for Map {
+    fn bounds(&self) -> Rect {
+        self.board.bounds()
+    }
+    fn clearance(&self, point: Position2d) -> f64 {
+        self.obstacles
+            .iter()
+            .fold(self.board.clearance(point), |room, o| {
+                room.min(o.distance(point))
+            })
+    }
+    fn trace(&self, renderer: &mut dyn Renderer) {
+        self.board.trace(renderer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn presets_are_playable() {
+        for name in Map::preset_names() {
+            let map = Map::preset(name).unwrap();
+            assert_eq!(map.name(), name);
+            assert_eq!(Map::from_json(&map.to_json()), Ok(map));
+        }
+        assert_eq!(Map::preset("nowhere"), None);
+    }
+    #[test]
+    fn obstacles_take_room() {
+        let map = Map::preset("arena").unwrap();
+        assert!(!map.contains(&Circle::new(360.0, 360.0, 10.0)));
+        assert!(map.contains(&Circle::new(360.0, 180.0, 90.0)));
+        assert!(!map.obstacles()[0].overlaps(&Circle::new(360.0, 180.0, 90.0)));
+        assert!(map.obstacles()[0].overlaps(&Circle::new(360.0, 180.0, 91.0)));
+        let spot = largest_empty_circle(&map, &[], f64::INFINITY).unwrap();
+        assert!((135.0 - spot.radius()).abs() < 1.0);
+    }
+    #[test]
+    fn reads_json() {
+        let json = r#"{"board": "800x600", "obstacles": ["100x100+50+50"]}"#;
+        let map = Map::from_json(json).unwrap();
+        assert_eq!(map.obstacles().len(), 1);
+        assert!(map.obstacles()[0].overlaps(&Circle::new(40.0, 40.0, 15.0)));
+        assert!(!map.obstacles()[0].overlaps(&Circle::new(40.0, 40.0, 14.0)));
+        assert!(Map::from_json(r#"{"board": "800x600", "obstacles": ["1000x1000"]}"#).is_err());
+        assert!(Map::from_json(r#"{"obstacles": []}"#).is_err());
+    }
+}
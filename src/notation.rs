@@ -0,0 +1,464 @@
+//! A human-readable record of a game, in the spirit of chess PGN.
+//!
+//! ```text
+//! [Board "1280x720"]
+//! [MinRadius "18"]
+//! [MaxRadius "360"]
+//! [Player "R" "red"]
+//! [Player "G" "green"]
+//! R (640,360) r120
+//! G pass
+//! ```
+//!
+//! The header gives the board (`WIDTHxHEIGHT`, with `+X+Y` if it is not at the origin,
+//! or another shape as described in `board`),
+//! the radius limits and the players in seating order.
+//! Games on a map add `[Map "NAME"]` and one `[Obstacle "SHAPE"]` per obstacle.
+//! `[NoRoom "manual"]` or `[NoRoom "give-up"]` is there if the game does not simply end
+//! once no circle fits, `[Scoring "..."]` for any scoring other than by area,
+//! `[TimeControl "turn 30"]` and `[Timeout "skip"]` for a time limit as described in `clock`,
+//! and `[Ranked "yes"]` for competitive games.
+//! Each move line starts with the player, by name or by color name,
+//! followed by a circle as `(X,Y) rRADIUS`, `pass` for giving up,
+//! or `skip` for letting the turn go without giving up.
+//! Lines starting with `;` are comments.
+use crate::board::GameBoard;
+use crate::color::Color;
+use crate::game::{Game, NoRoomRule};
+use crate::history::Move;
+use crate::map::{Map, Obstacle};
+use crate::scoring::ScoringRule;
+use crate::setup::GameSetup;
+use crate::shape::Circle;
+use std::fmt;
+use wasm_bindgen::JsValue;
+
+/// URL fragment prefix for shared games, as in `index.html#game=...`.
+const FRAGMENT_PREFIX: &str = "#game=";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotationError {
+    /// 1-based line number, or 0 for problems with the whole text.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl From<NotationError> for JsValue {
+    fn from(err: NotationError) -> JsValue {
+        JsValue::from_str(&err.to_string())
+    }
+}
+
+fn error<T>(line: usize, message: impl ToString) -> Result<T, NotationError> {
+    Err(NotationError {
+        line,
+        message: message.to_string(),
+    })
+}
+
+/// Writes the game from its start.
+///
+/// Returns `None` for a game resumed from a save, as the moves before it are unknown.
+pub fn write(game: &Game) -> Option<String> {
+    let moves = game.history().moves();
+    let placements = moves
+        .iter()
+        .filter(|m| matches!(m, Move::Place { .. }))
+        .count();
+    let give_ups = moves
+        .iter()
+        .filter(|m| matches!(m, Move::GiveUp { .. }))
+        .count();
+    let inactive = game.players().iter().filter(|p| !p.active()).count();
+    if placements != game.circles().len() || give_ups + game.out_of_room().count() != inactive {
+        return None;
+    }
+    let mut text = format!("[Board {}]\n", quote(&game.board().to_string()));
+    if !game.map().name().is_empty() {
+        text += &format!("[Map {}]\n", quote(game.map().name()));
+    }
+    for obstacle in game.map().obstacles() {
+        text += &format!("[Obstacle {}]\n", quote(&obstacle.to_string()));
+    }
+    text += &format!("[MinRadius \"{}\"]\n", game.min_radius());
+    text += &format!("[MaxRadius \"{}\"]\n", game.max_radius());
+    if game.no_room_rule() != NoRoomRule::EndGame {
+        text += &format!("[NoRoom \"{}\"]\n", game.no_room_rule().name());
+    }
+    if game.scoring_rule() != ScoringRule::Area {
+        text += &format!("[Scoring \"{}\"]\n", game.scoring_rule().name());
+    }
+    if let Some(control) = game.time_control() {
+        text += &format!("[TimeControl \"{}\"]\n", control);
+        text += &format!("[Timeout \"{}\"]\n", game.timeout_rule().name());
+    }
+    if game.ranked() {
+        text += "[Ranked \"yes\"]\n";
+    }
+    for player in game.players() {
+        text += &format!(
+            "[Player {} {}]\n",
+            quote(player.name()),
+            quote(&player.color().name())
+        );
+    }
+    //Names are only usable in moves if nobody else can be mistaken for them.
+    let token = |idx: usize| {
+        let player = &game.players()[idx];
+        let name = player.name();
+        let ambiguous = game
+            .players()
+            .iter()
+            .enumerate()
+            .any(|(other, p)| other != idx && (p.name() == name || p.color().name() == name));
+        if ambiguous || name.contains(['(', ')']) || name.starts_with(';') {
+            player.color().name()
+        } else {
+            name.to_string()
+        }
+    };
+    for m in moves {
+        match m {
+            Move::Place { player, circle, .. } => {
+                let pos = circle.position();
+                text += &format!(
+                    "{} ({},{}) r{}\n",
+                    token(*player),
+                    pos.x,
+                    pos.y,
+                    circle.radius()
+                );
+            }
+            Move::GiveUp { player } => text += &format!("{} pass\n", token(*player)),
+            Move::Skip { player } => text += &format!("{} skip\n", token(*player)),
+        }
+    }
+    Some(text)
+}
+
+/// Reads a game and plays its moves.
+pub fn read(text: &str) -> Result<Game, NotationError> {
+    let mut setup = GameSetup::new();
+    let mut board = None;
+    let mut map_name = String::new();
+    let mut obstacles = Vec::new();
+    let (mut min_radius, mut max_radius) = (None, None);
+    let mut game: Option<Game> = None;
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        if line.starts_with('[') {
+            if game.is_some() {
+                return error(line_no, "A header cannot come after the moves.");
+            }
+            let (key, values) = parse_tag(line).or_else(|msg| error(line_no, msg))?;
+            let value = |i: usize| -> Result<&str, NotationError> {
+                match values.get(i) {
+                    Some(value) => Ok(value),
+                    None => error(line_no, format!("{} needs {} value(s).", key, i + 1)),
+                }
+            };
+            match key.as_str() {
+                "Board" => board = Some(value(0)?.parse().or_else(|m| error(line_no, m))?),
+                "Map" => map_name = value(0)?.to_string(),
+                "Obstacle" => obstacles.push(value(0)?.parse().or_else(|m| error(line_no, m))?),
+                "MinRadius" => min_radius = Some(parse_number(value(0)?, line_no)?),
+                "MaxRadius" => max_radius = Some(parse_number(value(0)?, line_no)?),
+                "NoRoom" => {
+                    setup.set_no_room_rule(value(0)?.parse().or_else(|m| error(line_no, m))?)
+                }
+                "Scoring" => {
+                    setup.set_scoring_rule(value(0)?.parse().or_else(|m| error(line_no, m))?)
+                }
+                "TimeControl" => {
+                    setup.set_time_control(Some(value(0)?.parse().or_else(|m| error(line_no, m))?))
+                }
+                "Timeout" => {
+                    setup.set_timeout_rule(value(0)?.parse().or_else(|m| error(line_no, m))?)
+                }
+                "Ranked" => setup.set_ranked(value(0)? == "yes"),
+                "Player" => {
+                    let color: Color = value(1)?.parse().or_else(|m| error(line_no, m))?;
+                    setup
+                        .push_player(value(0)?, color)
+                        .or_else(|e| error(line_no, e))?;
+                }
+                //Unknown tags are kept for other tools, e.g. [Date "..."].
+                _ => {}
+            }
+            continue;
+        }
+        if game.is_none() {
+            game = Some(start(
+                &mut setup,
+                &map_name,
+                board.take(),
+                std::mem::take(&mut obstacles),
+                min_radius,
+                max_radius,
+                line_no,
+            )?);
+        }
+        let game = game.as_mut().unwrap();
+        let m = parse_move(game, line).or_else(|m| error(line_no, m))?;
+        game.play_move(m).or_else(|e| error(line_no, e))?;
+    }
+    match game {
+        Some(game) => Ok(game),
+        None => start(
+            &mut setup, &map_name, board, obstacles, min_radius, max_radius, 0,
+        ),
+    }
+}
+
+/// Builds the game once the header is over. Missing tags keep the usual values.
+fn start(
+    setup: &mut GameSetup,
+    map_name: &str,
+    board: Option<GameBoard>,
+    obstacles: Vec<Obstacle>,
+    min_radius: Option<f64>,
+    max_radius: Option<f64>,
+    line: usize,
+) -> Result<Game, NotationError> {
+    let map = Map::new(map_name, board.unwrap_or_default(), obstacles);
+    setup.set_map(map.or_else(|m| error(line, m))?);
+    let (min, max) = setup.radius_limits();
+    setup.set_radius_limits(min_radius.unwrap_or(min), max_radius.unwrap_or(max));
+    setup.build().or_else(|e| error(line, e))
+}
+
+/// A link to this page that opens `game`.
+pub fn share_link(game: &Game) -> Option<String> {
+    let text = write(game)?;
+    let location = web_sys::window()?.location();
+    let page = location.href().ok()?;
+    let page = page.split('#').next().unwrap_or_default();
+    let encoded = String::from(js_sys::encode_uri_component(&text));
+    Some(format!("{}{}{}", page, FRAGMENT_PREFIX, encoded))
+}
+
+/// The game shared through the URL fragment, if any.
+/// The fragment is dropped, so that a refresh does not go back to the shared position.
+pub fn take_from_url() -> Option<Result<Game, NotationError>> {
+    let window = web_sys::window()?;
+    let location = window.location();
+    let hash = location.hash().ok()?;
+    let encoded = hash.strip_prefix(FRAGMENT_PREFIX)?;
+    let text = js_sys::decode_uri_component(encoded).ok()?;
+    if let (Ok(history), Ok(path), Ok(search)) =
+        (window.history(), location.pathname(), location.search())
+    {
+        let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&(path + &search)));
+    }
+    Some(read(&String::from(text)))
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Splits `[Key "value" "value"]`.
+fn parse_tag(line: &str) -> Result<(String, Vec<String>), String> {
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or("A header must be in [brackets].")?;
+    let (key, mut rest) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+    let mut values = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let mut chars = rest.char_indices();
+        if chars.next().map(|(_, c)| c) != Some('"') {
+            return Err(format!("Values of {} must be \"quoted\".", key));
+        }
+        let mut value = String::new();
+        let mut end = None;
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                '"' => {
+                    end = Some(i);
+                    break;
+                }
+                _ => value.push(c),
+            }
+        }
+        let end = end.ok_or(format!("A value of {} is not closed.", key))?;
+        values.push(value);
+        rest = &rest[end + 1..];
+    }
+    Ok((key.to_string(), values))
+}
+
+fn parse_number(value: &str, line: usize) -> Result<f64, NotationError> {
+    value
+        .parse()
+        .or_else(|_| error(line, format!("{} is not a number.", value)))
+}
+
+/// Reads `WHO (X,Y) rRADIUS`, `WHO pass` or `WHO skip`.
+fn parse_move(game: &Game, line: &str) -> Result<Move, String> {
+    let keyword = ["pass", "skip"]
+        .iter()
+        .find_map(|&word| match line.strip_suffix(word) {
+            Some(who) if who.ends_with(char::is_whitespace) => Some((who.trim(), word)),
+            _ => None,
+        });
+    let (who, action) = match keyword {
+        Some(found) => found,
+        None => {
+            let open = line
+                .rfind('(')
+                .ok_or("A move must be a (X,Y) rRADIUS, pass or skip.")?;
+            (line[..open].trim(), &line[open..])
+        }
+    };
+    let player = game
+        .players()
+        .iter()
+        .position(|p| p.name() == who)
+        .or_else(|| game.players().iter().position(|p| p.color().name() == who))
+        .ok_or(format!("Nobody is called {}.", who))?;
+    match action {
+        "pass" => return Ok(Move::GiveUp { player }),
+        "skip" => return Ok(Move::Skip { player }),
+        _ => {}
+    }
+    let bad = || format!("A circle must look like (640,360) r120, not {}.", action);
+    let (center, radius) = action[1..].split_once(')').ok_or_else(bad)?;
+    let (x, y) = center.split_once(',').ok_or_else(bad)?;
+    let radius = radius.trim().strip_prefix('r').ok_or_else(bad)?;
+    let parse = |s: &str| s.trim().parse::<f64>().map_err(|_| bad());
+    Ok(Move::Place {
+        player,
+        circle: Circle::new(parse(x)?, parse(y)?, parse(radius)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{TimeControl, TimeoutRule};
+    use crate::shape::Position2d;
+    const SAMPLE: &str = "[Board \"1280x720\"]
+[MinRadius \"18\"]
+[MaxRadius \"360\"]
+[Player \"R\" \"red\"]
+[Player \"Mr. \\\"G\\\"\" \"green\"]
+R (640,360) r120
+; a comment
+Mr. \"G\" (100.5,100) r50
+R pass
+Mr. \"G\" pass
+";
+    #[test]
+    fn read_sample() {
+        let game = read(SAMPLE).unwrap();
+        assert_eq!(game.players()[1].name(), "Mr. \"G\"");
+        assert_eq!(game.circles().len(), 2);
+        assert_eq!(game.circles()[1].circle, Circle::new(100.5, 100.0, 50.0));
+        assert_eq!(game.current_player_idx(), None);
+        //Players can also be named by their colors.
+        let by_color = SAMPLE.replace("Mr. \"G\" pass", "green pass");
+        assert_eq!(read(&by_color).unwrap().current_player_idx(), None);
+    }
+    #[test]
+    fn round_trip() {
+        let game = read(SAMPLE).unwrap();
+        let text = write(&game).unwrap();
+        assert_eq!(text, SAMPLE.replace("; a comment\n", ""));
+        let again = read(&text).unwrap();
+        assert_eq!(write(&again).unwrap(), text);
+    }
+    #[test]
+    fn round_trip_offset_board_and_ambiguous_names() {
+        let mut setup = GameSetup::new();
+        setup.set_board(crate::shape::Rect::new(-10.0, 20.0, 800.0, 600.0));
+        setup.set_radius_limits(10.0, 200.0);
+        setup.set_no_room_rule(NoRoomRule::GiveUp);
+        setup.set_scoring_rule(ScoringRule::Territory);
+        setup.set_ranked(true);
+        setup.push_player("blue", Color::RED).unwrap();
+        setup.push_player("(B)", Color::BLUE).unwrap();
+        let mut game = setup.build().unwrap();
+        game.begin_circle(Position2d { x: 100.0, y: 100.0 })
+            .unwrap();
+        game.resize_circle(Position2d { x: 150.0, y: 100.0 })
+            .unwrap();
+        game.commit_circle().unwrap();
+        let text = write(&game).unwrap();
+        assert!(text.starts_with("[Board \"800x600-10+20\"]"));
+        assert!(text.ends_with("red (100,100) r50\n"));
+        let again = read(&text).unwrap();
+        assert_eq!(*again.board(), *game.board());
+        assert_eq!(again.max_radius(), 200.0);
+        assert_eq!(again.no_room_rule(), NoRoomRule::GiveUp);
+        assert_eq!(again.scoring_rule(), ScoringRule::Territory);
+        assert_eq!(again.players()[0].score(), game.players()[0].score());
+        assert!(again.ranked());
+        assert_eq!(again.circles(), game.circles());
+        assert_eq!(write(&again).unwrap(), text);
+    }
+    #[test]
+    fn round_trip_time_limits() {
+        let mut setup = GameSetup::default();
+        setup.set_time_bank(90500.0);
+        setup.set_timeout_rule(TimeoutRule::DropDraft);
+        let mut game = setup.build().unwrap();
+        game.skip().unwrap();
+        let text = write(&game).unwrap();
+        assert!(text.contains("[TimeControl \"bank 90.5\"]\n[Timeout \"drop\"]\n"));
+        assert!(text.ends_with(" skip\n"));
+        let again = read(&text).unwrap();
+        assert_eq!(again.time_control(), Some(TimeControl::Bank(90500.0)));
+        assert_eq!(again.timeout_rule(), TimeoutRule::DropDraft);
+        assert_eq!(again.history().moves(), game.history().moves());
+    }
+    #[test]
+    fn round_trip_maps() {
+        let mut setup = GameSetup::default();
+        setup.set_map(Map::preset("pillars").unwrap());
+        let game = setup.build().unwrap();
+        let text = write(&game).unwrap();
+        assert!(text.contains("[Map \"pillars\"]\n[Obstacle \"circle (320,180) r60\"]\n"));
+        let again = read(&text).unwrap();
+        assert_eq!(again.map(), game.map());
+        let blocked = text.replace("[MinRadius", "[Obstacle \"1280x720\"]\n[MinRadius");
+        assert!(read(&blocked).is_err());
+    }
+    #[test]
+    fn rejects_bad_radius_limits() {
+        for (min, max) in [("NaN", "360"), ("18", "inf"), ("-5", "360"), ("100", "10")] {
+            let text = SAMPLE
+                .replace("[MinRadius \"18\"]", &format!("[MinRadius \"{}\"]", min))
+                .replace("[MaxRadius \"360\"]", &format!("[MaxRadius \"{}\"]", max));
+            assert!(read(&text).is_err(), "{} {}", min, max);
+        }
+    }
+    #[test]
+    fn reports_the_bad_line() {
+        let text = SAMPLE.replace("R pass", "R (100,100) r30");
+        assert_eq!(read(&text).unwrap_err().line, 9);
+        let text = SAMPLE.replace("R pass", "Y pass");
+        assert_eq!(read(&text).unwrap_err().line, 9);
+        let text = SAMPLE.replace("R (640,360) r120", "R (640;360) r120");
+        assert_eq!(read(&text).unwrap_err().line, 6);
+        assert_eq!(read("[Player \"R\" \"red\"]").unwrap_err().line, 0);
+        //A board the solver could not start on is turned down at its header.
+        let text = SAMPLE.replace("1280x720", "1e9x1");
+        assert_eq!(read(&text).unwrap_err().line, 1);
+    }
+}
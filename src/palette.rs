@@ -0,0 +1,252 @@
+//! How the players are told apart on the screen: the colors they are shown in,
+//! and patterns drawn over their circles for those who cannot tell the colors apart.
+use crate::color::Color;
+use crate::renderer::Renderer;
+use crate::shape::{Circle, Position2d};
+use std::cell::Cell;
+use std::f64::consts::FRAC_1_SQRT_2;
+use std::fmt;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// Room between the lines or dots of a pattern, in board units.
+const PATTERN_SPACING: f64 = 10.0;
+const PATTERN_LINE_WIDTH: f64 = 1.5;
+const DOT_RADIUS: f64 = 2.0;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    Default,
+    /// Colors that stay apart with red-green color blindness, after Okabe and Ito.
+    Deuteranopia,
+    HighContrast,
+    /// Shades of gray, best with the patterns on.
+    Monochrome,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::Default,
+        Palette::Deuteranopia,
+        Palette::HighContrast,
+        Palette::Monochrome,
+    ];
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Default => "default",
+            Palette::Deuteranopia => "deuteranopia",
+            Palette::HighContrast => "high-contrast",
+            Palette::Monochrome => "monochrome",
+        }
+    }
+    /// The color `color` is shown in. Colors other than the player colors
+    /// are kept, or turned gray in `Monochrome`.
+    pub fn paint(self, color: Color) -> Color {
+        let table = match self {
+            Palette::Default => return color,
+            Palette::Deuteranopia => [
+                0xd55e00, 0x009e73, 0x0072b2, 0xf0e442, 0xcc79a7, 0xe69f00, 0x56b4e9, 0x882255,
+            ],
+            Palette::HighContrast => [
+                0xc00000, 0x007a00, 0x0033cc, 0xffd400, 0x7a00a8, 0xff7a00, 0x00a3a3, 0xe0007a,
+            ],
+            Palette::Monochrome => [
+                0x202020, 0xe0e0e0, 0x606060, 0xa0a0a0, 0x404040, 0xc0c0c0, 0x808080, 0xf8f8f8,
+            ],
+        };
+        match Color::PLAYER_COLORS.iter().position(|&c| c == color) {
+            Some(seat) => Color::from_hex(table[seat]),
+            None if self == Palette::Monochrome => color.to_gray(),
+            None => color,
+        }
+    }
+    /// A color for marks over `color` that stands out from it.
+    pub fn ink(self, color: Color) -> Color {
+        let paint = self.paint(color);
+        if paint.contrast_ratio(Color::WHITE) > paint.contrast_ratio(Color::BLACK) {
+            Color::WHITE.with_alpha(0.7)
+        } else {
+            Color::BLACK.with_alpha(0.5)
+        }
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Palette::ALL
+            .iter()
+            .copied()
+            .find(|palette| palette.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown palette: {}", s))
+    }
+}
+
+/// Marks drawn over the circles of a player, so that they can be told apart without color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    Solid,
+    Hatching,
+    Dots,
+    CrossHatch,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 4] = [
+        Pattern::Solid,
+        Pattern::Hatching,
+        Pattern::Dots,
+        Pattern::CrossHatch,
+    ];
+    /// The pattern of the `seat`th player.
+    pub fn of_seat(seat: usize) -> Pattern {
+        Pattern::ALL[seat % Pattern::ALL.len()]
+    }
+    /// Draws the pattern over `circle` in `ink`.
+    pub fn draw(self, circle: &Circle, ink: &str, renderer: &mut dyn Renderer) {
+        match self {
+            Pattern::Solid => {}
+            Pattern::Hatching | Pattern::CrossHatch => {
+                renderer.begin_path();
+                for (from, to) in self.lines(circle) {
+                    renderer.move_to(from.x, from.y);
+                    renderer.line_to(to.x, to.y);
+                }
+                renderer.stroke(ink, PATTERN_LINE_WIDTH, &[]);
+            }
+            Pattern::Dots => {
+                renderer.begin_path();
+                for dot in dots(circle) {
+                    renderer.move_to(dot.x + DOT_RADIUS, dot.y);
+                    renderer.arc(dot.x, dot.y, DOT_RADIUS, 0.0, std::f64::consts::PI * 2.0);
+                }
+                renderer.fill(ink);
+            }
+        }
+    }
+    /// The lines of a hatching, cut to `circle`.
+    fn lines(self, circle: &Circle) -> Vec<(Position2d, Position2d)> {
+        //Unit vectors along the lines.
+        let directions: &[(f64, f64)] = match self {
+            Pattern::Hatching => &[(FRAC_1_SQRT_2, FRAC_1_SQRT_2)],
+            Pattern::CrossHatch => &[
+                (FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+                (FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+            ],
+            Pattern::Solid | Pattern::Dots => &[],
+        };
+        let center = circle.position();
+        let radius = circle.radius();
+        let count = (radius / PATTERN_SPACING).floor() as i32;
+        let mut lines = Vec::new();
+        for &(dx, dy) in directions {
+            for k in -count..=count {
+                let offset = f64::from(k) * PATTERN_SPACING;
+                let half = (radius * radius - offset * offset).sqrt();
+                if half <= 0.0 {
+                    continue;
+                }
+                //Across the lines, then along them both ways.
+                let (mx, my) = (center.x - dy * offset, center.y + dx * offset);
+                lines.push((
+                    Position2d {
+                        x: mx - dx * half,
+                        y: my - dy * half,
+                    },
+                    Position2d {
+                        x: mx + dx * half,
+                        y: my + dy * half,
+                    },
+                ));
+            }
+        }
+        lines
+    }
+}
+
+/// The dots of a pattern that fit wholly within `circle`.
+fn dots(circle: &Circle) -> Vec<Position2d> {
+    let center = circle.position();
+    let reach = circle.radius() - DOT_RADIUS;
+    let count = (reach / PATTERN_SPACING).floor().max(0.0) as i32;
+    let mut dots = Vec::new();
+    for i in -count..=count {
+        for j in -count..=count {
+            let dot = Position2d {
+                x: center.x + f64::from(i) * PATTERN_SPACING,
+                y: center.y + f64::from(j) * PATTERN_SPACING,
+            };
+            if dot.distance(&center) <= reach {
+                dots.push(dot);
+            }
+        }
+    }
+    dots
+}
+
+thread_local! {
+    static PALETTE: Cell<Palette> = const { Cell::new(Palette::Default) };
+    static PATTERNS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The palette the players are shown in.
+pub fn palette() -> Palette {
+    PALETTE.with(Cell::get)
+}
+
+pub fn set_palette(palette: Palette) {
+    PALETTE.with(|p| p.set(palette));
+}
+
+/// Whether circles carry the pattern of their owner.
+pub fn patterns_shown() -> bool {
+    PATTERNS.with(Cell::get)
+}
+
+pub fn show_patterns(on: bool) {
+    PATTERNS.with(|p| p.set(on));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn paint_by_palette() {
+        assert_eq!(Palette::Default.paint(Color::LIGHT_RED), Color::LIGHT_RED);
+        let green = Palette::Deuteranopia.paint(Color::GREEN);
+        assert_eq!(green.to_string(), "#009e73");
+        assert_eq!(Palette::Monochrome.paint(Color::RED).to_string(), "#202020");
+        let other = Color::rgb(200, 0, 0);
+        assert_eq!(Palette::HighContrast.paint(other), other);
+        assert_eq!(Palette::Monochrome.paint(other), other.to_gray());
+        let ink = |color: Color| Palette::Monochrome.ink(color).to_string();
+        assert_eq!(ink(Color::RED), "rgba(255, 255, 255, 0.702)");
+        assert_eq!(ink(Color::GREEN), "rgba(0, 0, 0, 0.502)");
+        for palette in Palette::ALL {
+            assert_eq!(palette.name().parse(), Ok(palette));
+        }
+    }
+    #[test]
+    fn patterns_stay_inside() {
+        let circle = Circle::new(100.0, 50.0, 25.0);
+        let center = circle.position();
+        let hatching = Pattern::Hatching.lines(&circle);
+        assert_eq!(hatching.len(), 5);
+        assert_eq!(Pattern::CrossHatch.lines(&circle).len(), 10);
+        for (from, to) in hatching {
+            assert!((from.distance(&center) - 25.0).abs() < 1e-9);
+            assert!((to.distance(&center) - 25.0).abs() < 1e-9);
+        }
+        let dots = dots(&circle);
+        assert!(dots.iter().all(|dot| dot.distance(&center) <= 23.0));
+        assert_eq!(dots.len(), 21);
+        assert_eq!(Pattern::of_seat(5), Pattern::Hatching);
+    }
+}
@@ -0,0 +1,102 @@
+//! Repainting the canvas only when needed.
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+type FrameClosure = Closure<dyn FnMut(f64)>;
+
+/// A callback run on every animation frame of the browser until cancelled.
+pub struct Animation {
+    /// The pending frame request, `None` once cancelled.
+    request: Rc<Cell<Option<i32>>>,
+    /// Kept alive for the browser to call. It refers to itself to ask for the next frame.
+    closure: Rc<RefCell<Option<FrameClosure>>>,
+}
+
+impl Animation {
+    /// Calls `frame` with the ms since the previous frame, or 0 for the first one.
+    pub fn start(mut frame: impl FnMut(f64) + 'static) -> Result<Animation, JsValue> {
+        let request = Rc::new(Cell::new(None));
+        let closure: Rc<RefCell<Option<FrameClosure>>> = Rc::new(RefCell::new(None));
+        let (next_request, next_closure) = (request.clone(), closure.clone());
+        let mut last_time: Option<f64> = None;
+        *closure.borrow_mut() = Some(Closure::wrap(Box::new(move |now: f64| {
+            let elapsed = last_time.map_or(0.0, |last| now - last);
+            last_time = Some(now);
+            frame(elapsed);
+            //Cancelled while drawing.
+            if next_request.get().is_none() {
+                return;
+            }
+            if let Some(closure) = next_closure.borrow().as_ref() {
+                next_request.set(request_frame(closure).ok());
+            }
+        }) as Box<dyn FnMut(f64)>));
+        request.set(Some(request_frame(closure.borrow().as_ref().unwrap())?));
+        Ok(Animation { request, closure })
+    }
+    /// Stops asking for frames. The callback is dropped once no frame is pending.
+    pub fn cancel(self) {
+        if let Some(id) = self.request.take() {
+            let _ = web_sys::window().unwrap().cancel_animation_frame(id);
+        }
+        self.closure.borrow_mut().take();
+    }
+}
+
+fn request_frame(closure: &FrameClosure) -> Result<i32, JsValue> {
+    web_sys::window()
+        .unwrap()
+        .request_animation_frame(closure.as_ref().unchecked_ref())
+}
+
+/// An offscreen canvas holding what rarely changes, e.g. the circles already placed.
+/// It is drawn again only when its key changes, and copied onto the screen otherwise.
+pub struct Layer<K> {
+    canvas: web_sys::HtmlCanvasElement,
+    context: web_sys::CanvasRenderingContext2d,
+    key: Option<K>,
+}
+
+impl<K: PartialEq> Layer<K> {
+    pub fn new(document: &web_sys::Document) -> Result<Layer<K>, JsValue> {
+        let canvas = document
+            .create_element("canvas")?
+            .dyn_into::<web_sys::HtmlCanvasElement>()?;
+        let context = canvas
+            .get_context("2d")?
+            .unwrap()
+            .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
+        Ok(Layer {
+            canvas,
+            context,
+            key: None,
+        })
+    }
+    /// Replaces everything on `target` with the layer, after running `draw` on the layer
+    /// if `key` differs from the last time. The layer takes the size of `target`.
+    pub fn paint(
+        &mut self,
+        target: &web_sys::CanvasRenderingContext2d,
+        key: K,
+        draw: impl FnOnce(&web_sys::CanvasRenderingContext2d),
+    ) {
+        let screen = target.canvas().expect("A context always has its canvas.");
+        let resized =
+            self.canvas.width() != screen.width() || self.canvas.height() != screen.height();
+        if resized {
+            self.canvas.set_width(screen.width());
+            self.canvas.set_height(screen.height());
+        }
+        if resized || self.key.as_ref() != Some(&key) {
+            draw(&self.context);
+            self.key = Some(key);
+        }
+        target.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+        target.clear_rect(0.0, 0.0, screen.width().into(), screen.height().into());
+        target
+            .draw_image_with_html_canvas_element(&self.canvas, 0.0, 0.0)
+            .unwrap();
+    }
+}
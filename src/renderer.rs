@@ -0,0 +1,336 @@
+//! Drawing surfaces: the canvas on the page, or an SVG picture for sharing.
+use crate::shape::Rect;
+use std::f64::consts::PI;
+use std::fmt::Write;
+use wasm_bindgen::JsValue;
+
+/// Something to draw paths and text on, in board coordinates.
+/// Styles are CSS colors, and text is centered on the given point.
+pub trait Renderer {
+    fn begin_path(&mut self);
+    fn move_to(&mut self, x: f64, y: f64);
+    fn line_to(&mut self, x: f64, y: f64);
+    /// Clockwise from the `start` angle to the `end` one, in radians.
+    /// It is joined to the path so far with a line.
+    fn arc(&mut self, x: f64, y: f64, radius: f64, start: f64, end: f64);
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64);
+    fn close_path(&mut self);
+    /// Limits what is drawn to the current path, until the matching `restore`.
+    fn clip(&mut self);
+    /// Remembers the clipping, for `restore` to go back to.
+    fn save(&mut self);
+    fn restore(&mut self);
+    fn fill(&mut self, style: &str);
+    /// `dash` gives the lengths of the dashes and the gaps, or is empty for a solid line.
+    fn stroke(&mut self, style: &str, width: f64, dash: &[f64]);
+    fn fill_rect(&mut self, rect: &Rect, style: &str);
+    fn fill_text(&mut self, text: &str, x: f64, y: f64, font: &str, style: &str);
+    fn stroke_text(&mut self, text: &str, x: f64, y: f64, font: &str, style: &str, width: f64);
+}
+
+/// Draws on a canvas of the page.
+pub struct CanvasRenderer<'a> {
+    context: &'a web_sys::CanvasRenderingContext2d,
+}
+
+impl<'a> CanvasRenderer<'a> {
+    pub fn new(context: &'a web_sys::CanvasRenderingContext2d) -> CanvasRenderer<'a> {
+        CanvasRenderer { context }
+    }
+    fn set_text_style(&self, font: &str) {
+        self.context.set_font(font);
+        self.context.set_text_align("center");
+        self.context.set_text_baseline("middle");
+    }
+}
+
+impl Renderer for CanvasRenderer<'_> {
+    fn begin_path(&mut self) {
+        self.context.begin_path();
+    }
+    fn move_to(&mut self, x: f64, y: f64) {
+        self.context.move_to(x, y);
+    }
+    fn line_to(&mut self, x: f64, y: f64) {
+        self.context.line_to(x, y);
+    }
+    fn arc(&mut self, x: f64, y: f64, radius: f64, start: f64, end: f64) {
+        self.context.arc(x, y, radius, start, end).unwrap();
+    }
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        self.context.rect(x, y, width, height);
+    }
+    fn close_path(&mut self) {
+        self.context.close_path();
+    }
+    fn clip(&mut self) {
+        self.context.clip();
+    }
+    fn save(&mut self) {
+        self.context.save();
+    }
+    fn restore(&mut self) {
+        self.context.restore();
+    }
+    fn fill(&mut self, style: &str) {
+        self.context.set_fill_style_str(style);
+        self.context.fill();
+    }
+    fn stroke(&mut self, style: &str, width: f64, dash: &[f64]) {
+        let dashes: js_sys::Array = dash.iter().map(|&d| JsValue::from(d)).collect();
+        self.context.set_line_dash(&dashes).unwrap();
+        self.context.set_stroke_style_str(style);
+        self.context.set_line_width(width);
+        self.context.stroke();
+    }
+    fn fill_rect(&mut self, rect: &Rect, style: &str) {
+        let origin = rect.position();
+        self.context.set_fill_style_str(style);
+        self.context
+            .fill_rect(origin.x, origin.y, rect.width(), rect.height());
+    }
+    fn fill_text(&mut self, text: &str, x: f64, y: f64, font: &str, style: &str) {
+        self.set_text_style(font);
+        self.context.set_fill_style_str(style);
+        self.context
+            .fill_text(text, x, y)
+            .expect("Failed to print text.");
+    }
+    fn stroke_text(&mut self, text: &str, x: f64, y: f64, font: &str, style: &str, width: f64) {
+        self.set_text_style(font);
+        self.context.set_line_dash(&js_sys::Array::new()).unwrap();
+        self.context.set_stroke_style_str(style);
+        self.context.set_line_width(width);
+        self.context
+            .stroke_text(text, x, y)
+            .expect("Failed to stroke text.");
+    }
+}
+
+/// Writes an SVG picture of the area `view`.
+pub struct SvgRenderer {
+    view: Rect,
+    body: String,
+    /// Path data since the last `begin_path`.
+    path: String,
+    /// Clip paths made so far, for unique ids.
+    clips: usize,
+    /// The groups opened by `clip` since each `save`.
+    saved: Vec<usize>,
+}
+
+impl SvgRenderer {
+    pub fn new(view: Rect) -> SvgRenderer {
+        SvgRenderer {
+            view,
+            body: String::new(),
+            path: String::new(),
+            clips: 0,
+            saved: Vec::new(),
+        }
+    }
+    /// The whole SVG document.
+    pub fn finish(mut self) -> String {
+        while !self.saved.is_empty() {
+            self.restore();
+        }
+        let origin = self.view.position();
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">\n{}</svg>\n",
+            number(origin.x),
+            number(origin.y),
+            number(self.view.width()),
+            number(self.view.height()),
+            number(self.view.width()),
+            number(self.view.height()),
+            self.body
+        )
+    }
+    fn text(&mut self, text: &str, x: f64, y: f64, font: &str, paint: &str) {
+        writeln!(
+            self.body,
+            "<text x=\"{}\" y=\"{}\" style=\"font: {}\" text-anchor=\"middle\" dominant-baseline=\"middle\" {}>{}</text>",
+            number(x),
+            number(y),
+            escape(font),
+            paint,
+            escape(text)
+        )
+        .unwrap();
+    }
+}
+
+impl Renderer for SvgRenderer {
+    fn begin_path(&mut self) {
+        self.path.clear();
+    }
+    fn move_to(&mut self, x: f64, y: f64) {
+        write!(self.path, "M{} {} ", number(x), number(y)).unwrap();
+    }
+    fn line_to(&mut self, x: f64, y: f64) {
+        write!(self.path, "L{} {} ", number(x), number(y)).unwrap();
+    }
+    fn arc(&mut self, x: f64, y: f64, radius: f64, start: f64, end: f64) {
+        let point = |angle: f64| (x + radius * angle.cos(), y + radius * angle.sin());
+        let (from_x, from_y) = point(start);
+        if self.path.is_empty() {
+            self.move_to(from_x, from_y);
+        } else {
+            self.line_to(from_x, from_y);
+        }
+        //An SVG arc cannot go all the way round, so go by half turns at most.
+        let mut from = start;
+        while from < end {
+            let to = (from + PI).min(end);
+            let (to_x, to_y) = point(to);
+            let r = number(radius);
+            write!(
+                self.path,
+                "A{} {} 0 0 1 {} {} ",
+                r,
+                r,
+                number(to_x),
+                number(to_y)
+            )
+            .unwrap();
+            from = to;
+        }
+    }
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        self.move_to(x, y);
+        self.line_to(x + width, y);
+        self.line_to(x + width, y + height);
+        self.line_to(x, y + height);
+        self.close_path();
+    }
+    fn close_path(&mut self) {
+        self.path.push_str("Z ");
+    }
+    fn clip(&mut self) {
+        self.clips += 1;
+        writeln!(
+            self.body,
+            "<clipPath id=\"clip{}\"><path d=\"{}\"/></clipPath>\n<g clip-path=\"url(#clip{})\">",
+            self.clips,
+            self.path.trim_end(),
+            self.clips
+        )
+        .unwrap();
+        if let Some(groups) = self.saved.last_mut() {
+            *groups += 1;
+        }
+    }
+    fn save(&mut self) {
+        self.saved.push(0);
+    }
+    fn restore(&mut self) {
+        for _ in 0..self.saved.pop().unwrap_or(0) {
+            self.body.push_str("</g>\n");
+        }
+    }
+    fn fill(&mut self, style: &str) {
+        writeln!(
+            self.body,
+            "<path d=\"{}\" fill=\"{}\"/>",
+            self.path.trim_end(),
+            escape(style)
+        )
+        .unwrap();
+    }
+    fn stroke(&mut self, style: &str, width: f64, dash: &[f64]) {
+        let dash = if dash.is_empty() {
+            String::new()
+        } else {
+            let lengths: Vec<String> = dash.iter().map(|&d| number(d)).collect();
+            format!(" stroke-dasharray=\"{}\"", lengths.join(" "))
+        };
+        writeln!(
+            self.body,
+            "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{}/>",
+            self.path.trim_end(),
+            escape(style),
+            number(width),
+            dash
+        )
+        .unwrap();
+    }
+    fn fill_rect(&mut self, rect: &Rect, style: &str) {
+        let origin = rect.position();
+        writeln!(
+            self.body,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            number(origin.x),
+            number(origin.y),
+            number(rect.width()),
+            number(rect.height()),
+            escape(style)
+        )
+        .unwrap();
+    }
+    fn fill_text(&mut self, text: &str, x: f64, y: f64, font: &str, style: &str) {
+        let paint = format!("fill=\"{}\"", escape(style));
+        self.text(text, x, y, font, &paint);
+    }
+    fn stroke_text(&mut self, text: &str, x: f64, y: f64, font: &str, style: &str, width: f64) {
+        let paint = format!(
+            "fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"",
+            escape(style),
+            number(width)
+        );
+        self.text(text, x, y, font, &paint);
+    }
+}
+
+/// Two decimals are plenty for the eye, and keep the file small.
+fn number(value: f64) -> String {
+    //Adding zero turns -0 into 0.
+    format!("{}", (value * 100.0).round() / 100.0 + 0.0)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn svg_paths_and_text() {
+        let mut svg = SvgRenderer::new(Rect::new(0.0, 0.0, 200.0, 100.0));
+        svg.begin_path();
+        svg.arc(50.0, 50.0, 20.0, 0.0, PI * 2.0);
+        svg.fill("#ff0000");
+        svg.stroke("#000000", 2.0, &[4.0, 4.0]);
+        svg.fill_text("A <B> & \"C\"", 100.0, 50.0, "20px system-ui", "#000000");
+        let text = svg.finish();
+        assert!(
+            text.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 200 100\"")
+        );
+        assert!(text.contains(
+            "<path d=\"M70 50 A20 20 0 0 1 30 50 A20 20 0 0 1 70 50\" fill=\"#ff0000\"/>"
+        ));
+        assert!(text.contains("stroke-dasharray=\"4 4\""));
+        assert!(text.contains(">A &lt;B&gt; &amp; &quot;C&quot;</text>"));
+    }
+    #[test]
+    fn svg_clips_until_restored() {
+        let mut svg = SvgRenderer::new(Rect::new(0.0, 0.0, 200.0, 100.0));
+        svg.save();
+        svg.begin_path();
+        svg.rect(0.0, 0.0, 50.0, 50.0);
+        svg.clip();
+        svg.fill_rect(&Rect::new(0.0, 0.0, 200.0, 100.0), "#ffffff");
+        svg.restore();
+        svg.save();
+        svg.clip();
+        let text = svg.finish();
+        assert!(text.contains(
+            "<clipPath id=\"clip1\"><path d=\"M0 0 L50 0 L50 50 L0 50 Z\"/></clipPath>\n<g clip-path=\"url(#clip1)\">\n<rect"
+        ));
+        assert!(text.contains("clip2"));
+        assert_eq!(text.matches("<g ").count(), text.matches("</g>").count());
+    }
+}
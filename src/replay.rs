@@ -0,0 +1,373 @@
+use crate::game::{Game, GameError};
+use crate::history::Move;
+use crate::session::with_session;
+use crate::shape::Circle;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Shortest and longest pause between two steps while autoplaying, in ms.
+/// Real gaps are used in between, so quick and slow turns still feel different.
+const MIN_GAP: f64 = 200.0;
+const MAX_GAP: f64 = 2000.0;
+
+/// Something that happened during a game.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Entry {
+    /// A placement or a give-up.
+    Move(Move),
+    /// A circle that was let go but could not be placed.
+    Rejected {
+        player: usize,
+        circle: Circle,
+        error: GameError,
+    },
+    Undo,
+    Redo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Record {
+    /// Milliseconds since the Unix epoch.
+    pub time: f64,
+    pub entry: Entry,
+}
+
+/// Everything that happened since a game started, in order.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    initial: Game,
+    records: Vec<Record>,
+}
+
+impl Recording {
+    pub fn new(initial: &Game) -> Recording {
+        let mut initial = initial.clone();
+        initial.cancel_circle();
+        Recording {
+            initial,
+            records: Vec::new(),
+        }
+    }
+    pub fn record(&mut self, time: f64, entry: Entry) {
+        self.records.push(Record { time, entry });
+    }
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+    /// The game as it was after the first `step` records.
+    pub fn game_at(&self, step: usize) -> Game {
+        let mut game = self.initial.clone();
+        for record in self.records.iter().take(step) {
+            //Every record was accepted when it happened, so replaying it cannot fail.
+            let _ = match record.entry {
+                Entry::Move(m) => game.play_move(m),
+                Entry::Rejected { .. } => Ok(Vec::new()),
+                Entry::Undo => game.undo(),
+                Entry::Redo => game.redo(),
+            };
+        }
+        game
+    }
+}
+
+/// Steps through a recording.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    recording: Recording,
+    step: usize,
+    game: Game,
+    playing: bool,
+    speed: f64,
+    waited: f64,
+}
+
+impl Replay {
+    /// Starts from the beginning, paused.
+    pub fn new(recording: Recording) -> Replay {
+        let game = recording.game_at(0);
+        Replay {
+            recording,
+            step: 0,
+            game,
+            playing: false,
+            speed: 1.0,
+            waited: 0.0,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.recording.records().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// How many records have been replayed.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+    /// The game after the current step.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+    /// The record replayed last, if any.
+    pub fn last_record(&self) -> Option<&Record> {
+        self.step
+            .checked_sub(1)
+            .and_then(|idx| self.recording.records().get(idx))
+    }
+    pub fn seek(&mut self, step: usize) {
+        let step = step.min(self.len());
+        if step != self.step {
+            self.step = step;
+            self.game = self.recording.game_at(step);
+        }
+        self.waited = 0.0;
+    }
+    pub fn step_forward(&mut self) {
+        self.seek(self.step + 1);
+    }
+    pub fn step_back(&mut self) {
+        self.seek(self.step.saturating_sub(1));
+    }
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+    /// Autoplays at `speed` times the pace of the real game.
+    pub fn set_speed(&mut self, speed: f64) {
+        if speed > 0.0 {
+            self.speed = speed;
+        }
+    }
+    /// Autoplays from the current step, or from the start if already at the end.
+    pub fn play(&mut self) {
+        if self.step == self.len() {
+            self.seek(0);
+        }
+        self.playing = true;
+    }
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+    /// Lets `elapsed` ms pass. Returns whether the step changed.
+    pub fn advance(&mut self, elapsed: f64) -> bool {
+        if !self.playing {
+            return false;
+        }
+        let before = self.step;
+        self.waited += elapsed * self.speed;
+        while self.step < self.len() {
+            let gap = self.gap(self.step);
+            if self.waited < gap {
+                break;
+            }
+            self.waited -= gap;
+            self.step += 1;
+        }
+        if self.step == self.len() {
+            self.playing = false;
+        }
+        if self.step != before {
+            self.game = self.recording.game_at(self.step);
+        }
+        self.step != before
+    }
+    /// How long to wait before replaying the record at `idx`.
+    fn gap(&self, idx: usize) -> f64 {
+        let records = self.recording.records();
+        let previous = if idx == 0 {
+            records[0].time
+        } else {
+            records[idx - 1].time
+        };
+        (records[idx].time - previous).clamp(MIN_GAP, MAX_GAP)
+    }
+}
+
+/// The bar of buttons and the timeline shown while replaying.
+pub struct ReplayControls {
+    bar: web_sys::HtmlElement,
+    timeline: web_sys::HtmlInputElement,
+    play: web_sys::HtmlButtonElement,
+    _closures: Vec<Closure<dyn FnMut()>>,
+}
+
+impl ReplayControls {
+    pub fn new(len: usize) -> Result<ReplayControls, JsValue> {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let bar = document
+            .create_element("div")?
+            .dyn_into::<web_sys::HtmlElement>()?;
+        bar.set_id("replay");
+        let mut closures = Vec::new();
+        let mut add_button = |label: &str, action: fn()| -> Result<_, JsValue> {
+            let button = document
+                .create_element("button")?
+                .dyn_into::<web_sys::HtmlButtonElement>()?;
+            button.set_inner_text(label);
+            let closure = Closure::wrap(Box::new(action) as Box<dyn FnMut()>);
+            button.set_onclick(Some(closure.as_ref().unchecked_ref()));
+            closures.push(closure);
+            bar.append_child(&button)?;
+            Ok(button)
+        };
+        add_button("⏮", || {
+            with_session(|s| s.replay_seek(0));
+        })?;
+        add_button("|◀", || {
+            with_session(|s| s.replay_step(-1));
+        })?;
+        let play = add_button("▶", || {
+            with_session(|s| s.replay_toggle());
+        })?;
+        add_button("▶|", || {
+            with_session(|s| s.replay_step(1));
+        })?;
+        add_button("⏭", || {
+            with_session(|s| s.replay_seek(usize::MAX));
+        })?;
+        let timeline = document
+            .create_element("input")?
+            .dyn_into::<web_sys::HtmlInputElement>()?;
+        timeline.set_type("range");
+        timeline.set_min("0");
+        timeline.set_max(&len.to_string());
+        timeline.set_value("0");
+        {
+            let target = timeline.clone();
+            let closure = Closure::wrap(Box::new(move || {
+                let step = target.value_as_number() as usize;
+                with_session(|s| s.replay_seek(step));
+            }) as Box<dyn FnMut()>);
+            timeline.set_oninput(Some(closure.as_ref().unchecked_ref()));
+            closures.push(closure);
+        }
+        bar.append_child(&timeline)?;
+        let speed = document
+            .create_element("select")?
+            .dyn_into::<web_sys::HtmlSelectElement>()?;
+        for value in ["0.5", "1", "2", "4", "8"].iter() {
+            let option =
+                web_sys::HtmlOptionElement::new_with_text_and_value(&format!("{}x", value), value)?;
+            speed.append_child(&option)?;
+        }
+        speed.set_value("1");
+        {
+            let target = speed.clone();
+            let closure = Closure::wrap(Box::new(move || {
+                let speed = target.value().parse().unwrap_or(1.0);
+                with_session(|s| s.replay_set_speed(speed));
+            }) as Box<dyn FnMut()>);
+            speed.set_onchange(Some(closure.as_ref().unchecked_ref()));
+            closures.push(closure);
+        }
+        bar.append_child(&speed)?;
+        let canvas = document
+            .get_element_by_id("canvas")
+            .expect("Need an canvase element with id=\"canvas\".");
+        canvas
+            .parent_node()
+            .expect("The canvas must be in the document.")
+            .insert_before(&bar, Some(&canvas))?;
+        Ok(ReplayControls {
+            bar,
+            timeline,
+            play,
+            _closures: closures,
+        })
+    }
+    /// Shows where `replay` is.
+    pub fn update(&self, replay: &Replay) {
+        self.timeline.set_value(&replay.step().to_string());
+        self.play
+            .set_inner_text(if replay.is_playing() { "⏸" } else { "▶" });
+    }
+    pub fn remove(&self) {
+        self.bar.remove();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::setup::GameSetup;
+    use crate::shape::Position2d;
+    fn recorded_game() -> Recording {
+        let mut game = GameSetup::default().build().unwrap();
+        let mut recording = Recording::new(&game);
+        let circle = Circle::new(100.0, 100.0, 50.0);
+        let m = Move::Place { player: 0, circle };
+        game.play_move(m).unwrap();
+        recording.record(1000.0, Entry::Move(m));
+        recording.record(
+            1500.0,
+            Entry::Rejected {
+                player: 1,
+                circle: Circle::new(120.0, 100.0, 30.0),
+                error: GameError::Overlapped,
+            },
+        );
+        game.give_up().unwrap();
+        recording.record(6000.0, Entry::Move(Move::GiveUp { player: 1 }));
+        game.undo().unwrap();
+        recording.record(6100.0, Entry::Undo);
+        game.begin_circle(Position2d { x: 600.0, y: 300.0 })
+            .unwrap();
+        game.resize_circle(Position2d { x: 700.0, y: 300.0 })
+            .unwrap();
+        game.commit_circle().unwrap();
+        recording.record(9000.0, Entry::Move(*game.history().moves().last().unwrap()));
+        recording
+    }
+    #[test]
+    fn scoreboard_at_each_step() {
+        let recording = recorded_game();
+        assert_eq!(recording.game_at(0).circles().len(), 0);
+        assert_eq!(recording.game_at(1).players()[0].score() as u32, 7853);
+        assert_eq!(recording.game_at(2).current_player_idx(), Some(1));
+        assert!(!recording.game_at(3).players()[1].active());
+        assert!(recording.game_at(4).players()[1].active());
+        let end = recording.game_at(5);
+        assert_eq!(end.players()[1].score() as u32, 31415);
+        assert_eq!(end.current_player_idx(), Some(2));
+    }
+    #[test]
+    fn stepping_and_seeking() {
+        let mut replay = Replay::new(recorded_game());
+        assert_eq!(replay.len(), 5);
+        replay.step_back();
+        assert_eq!(replay.step(), 0);
+        assert_eq!(replay.last_record(), None);
+        replay.step_forward();
+        replay.step_forward();
+        assert!(matches!(
+            replay.last_record().unwrap().entry,
+            Entry::Rejected { .. }
+        ));
+        replay.seek(100);
+        assert_eq!(replay.step(), 5);
+        assert_eq!(replay.game().circles().len(), 2);
+    }
+    #[test]
+    fn autoplay_follows_real_gaps() {
+        let mut replay = Replay::new(recorded_game());
+        assert!(!replay.advance(10_000.0));
+        replay.set_speed(2.0);
+        replay.play();
+        //The first record waits the shortest gap.
+        assert!(!replay.advance(50.0));
+        assert!(replay.advance(50.0));
+        assert_eq!(replay.step(), 1);
+        //500ms at double speed.
+        assert!(replay.advance(250.0));
+        assert_eq!(replay.step(), 2);
+        //4.5s is capped to 2s.
+        assert!(!replay.advance(900.0));
+        assert!(replay.advance(100.0));
+        assert_eq!(replay.step(), 3);
+        assert!(replay.advance(10_000.0));
+        assert_eq!(replay.step(), 5);
+        assert!(!replay.is_playing());
+    }
+}
@@ -0,0 +1,448 @@
+use crate::board::{Board, GameBoard};
+use crate::bot::Bot;
+use crate::clock::{TimeControl, TimeoutRule};
+use crate::game::{self, Game, NoRoomRule, PlacedCircle, Player};
+use crate::history::UndoRule;
+use crate::map::Map;
+use crate::scoring::ScoringRule;
+use crate::setup::{check_radius_limits, GameSetup, MAX_PLAYERS, MIN_PLAYERS};
+use crate::shape::Circle;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Bumped whenever the format changes in a way older readers cannot follow.
+/// Version 2 added the board `shape` and `obstacles`, which version 1 saves go without.
+pub const SCHEMA_VERSION: u32 = 2;
+const AUTOSAVE_KEY: &str = "game-of-compass.autosave";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    Json(String),
+    /// Written by a newer version of the game.
+    UnsupportedVersion(u32),
+    Invalid(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Json(msg) => write!(f, "Broken save: {}", msg),
+            LoadError::UnsupportedVersion(version) => write!(
+                f,
+                "The save is of version {}, but only up to {} can be read.",
+                version, SCHEMA_VERSION
+            ),
+            LoadError::Invalid(msg) => write!(f, "Invalid save: {}", msg),
+        }
+    }
+}
+
+impl From<LoadError> for JsValue {
+    fn from(err: LoadError) -> JsValue {
+        JsValue::from_str(&err.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardSnapshot {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Any other shape than a rectangle, as written in the notation.
+    /// The numbers above are then its bounds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shape: Option<String>,
+    /// The name of the map the board comes from, if any.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub map: String,
+    /// As written in the notation.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub obstacles: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSnapshot {
+    pub name: String,
+    /// Color name, e.g. `"red"`.
+    pub color: String,
+    /// Worked out again from the circles when loading.
+    pub score: f64,
+    pub active: bool,
+    /// Left out for people.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bot: Option<BotSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BotSnapshot {
+    /// Bot name, e.g. `"greedy"`.
+    pub kind: String,
+    pub think_delay: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CircleSnapshot {
+    pub x: f64,
+    pub y: f64,
+    pub radius: f64,
+    /// Index into the players.
+    pub owner: usize,
+}
+
+/// Everything needed to carry on a game, in a stable serializable form.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub board: BoardSnapshot,
+    pub min_radius: f64,
+    pub max_radius: f64,
+    #[serde(default = "default_undo_rule")]
+    pub undo_rule: UndoRule,
+    #[serde(default = "default_no_room_rule")]
+    pub no_room_rule: NoRoomRule,
+    #[serde(default = "default_scoring_rule")]
+    pub scoring_rule: ScoringRule,
+    /// As written in the notation. The time already spent is not kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_control: Option<String>,
+    #[serde(default = "default_timeout_rule")]
+    pub timeout_rule: TimeoutRule,
+    #[serde(default)]
+    pub ranked: bool,
+    pub players: Vec<PlayerSnapshot>,
+    /// In the order they were placed.
+    pub circles: Vec<CircleSnapshot>,
+    pub current_player: Option<usize>,
+}
+
+fn default_undo_rule() -> UndoRule {
+    UndoRule::Unlimited
+}
+
+fn default_no_room_rule() -> NoRoomRule {
+    NoRoomRule::EndGame
+}
+
+fn default_scoring_rule() -> ScoringRule {
+    ScoringRule::Area
+}
+
+fn default_timeout_rule() -> TimeoutRule {
+    TimeoutRule::Skip
+}
+
+impl Snapshot {
+    pub fn of(game: &Game) -> Snapshot {
+        let bounds = game.board().bounds();
+        let shape = match game.board() {
+            GameBoard::Rect(_) => None,
+            board => Some(board.to_string()),
+        };
+        Snapshot {
+            version: SCHEMA_VERSION,
+            board: BoardSnapshot {
+                x: bounds.position().x,
+                y: bounds.position().y,
+                width: bounds.width(),
+                height: bounds.height(),
+                shape,
+                map: game.map().name().to_string(),
+                obstacles: game
+                    .map()
+                    .obstacles()
+                    .iter()
+                    .map(|o| o.to_string())
+                    .collect(),
+            },
+            min_radius: game.min_radius(),
+            max_radius: game.max_radius(),
+            undo_rule: game.history().rule(),
+            no_room_rule: game.no_room_rule(),
+            scoring_rule: game.scoring_rule(),
+            time_control: game.time_control().map(|c| c.to_string()),
+            timeout_rule: game.timeout_rule(),
+            ranked: game.ranked(),
+            players: game
+                .players()
+                .iter()
+                .map(|p| PlayerSnapshot {
+                    name: p.name().to_string(),
+                    color: p.color().name(),
+                    score: p.score(),
+                    active: p.active(),
+                    bot: p.bot().map(|bot| BotSnapshot {
+                        kind: bot.kind.name().to_string(),
+                        think_delay: bot.think_delay,
+                    }),
+                })
+                .collect(),
+            circles: game
+                .circles()
+                .iter()
+                .map(|c| CircleSnapshot {
+                    x: c.circle.position().x,
+                    y: c.circle.position().y,
+                    radius: c.circle.radius(),
+                    owner: c.owner,
+                })
+                .collect(),
+            current_player: game.current_player_idx(),
+        }
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("A snapshot is always serializable.")
+    }
+    pub fn from_json(json: &str) -> Result<Snapshot, LoadError> {
+        //Look at the version first, so that a newer save is not reported as broken.
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: u32,
+        }
+        let versioned: Versioned =
+            serde_json::from_str(json).map_err(|e| LoadError::Json(e.to_string()))?;
+        if versioned.version > SCHEMA_VERSION {
+            return Err(LoadError::UnsupportedVersion(versioned.version));
+        }
+        serde_json::from_str(json).map_err(|e| LoadError::Json(e.to_string()))
+    }
+    pub fn into_game(self) -> Result<Game, LoadError> {
+        let invalid = |msg: &str| Err(LoadError::Invalid(msg.to_string()));
+        let count = self.players.len();
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&count) {
+            return invalid("wrong number of players");
+        }
+        if self.current_player.is_some_and(|idx| idx >= count) {
+            return invalid("the current player does not exist");
+        }
+        if self
+            .current_player
+            .is_some_and(|idx| !self.players[idx].active)
+        {
+            return invalid("the current player is out");
+        }
+        if self.circles.iter().any(|c| c.owner >= count) {
+            return invalid("a circle belongs to nobody");
+        }
+        let (min_radius, max_radius) = (self.min_radius, self.max_radius);
+        check_radius_limits(min_radius, max_radius)
+            .map_err(|e| LoadError::Invalid(e.to_string()))?;
+        //Seat the players as a setup would, so that names and colors are checked alike.
+        let mut setup = GameSetup::new();
+        for p in self.players.iter() {
+            let color = p.color.parse().map_err(LoadError::Invalid)?;
+            let seated = match p.bot.as_ref() {
+                Some(bot) => {
+                    let bot = Bot {
+                        kind: bot.kind.parse().map_err(LoadError::Invalid)?,
+                        think_delay: bot.think_delay,
+                    };
+                    setup.push_bot(&p.name, color, bot)
+                }
+                None => setup.push_player(&p.name, color),
+            };
+            seated.map_err(|e| LoadError::Invalid(e.to_string()))?;
+        }
+        let players = setup
+            .players()
+            .iter()
+            .zip(self.players.iter())
+            .map(|(seat, p)| {
+                Player::restore(seat.name(), seat.color(), p.score, p.active).with_bot(seat.bot())
+            })
+            .collect();
+        let board = match self.board.shape.as_ref() {
+            Some(shape) => shape.parse().map_err(LoadError::Invalid)?,
+            None => GameBoard::rect(
+                self.board.x,
+                self.board.y,
+                self.board.width,
+                self.board.height,
+            )
+            .map_err(LoadError::Invalid)?,
+        };
+        let obstacles = self
+            .board
+            .obstacles
+            .iter()
+            .map(|o| o.parse())
+            .collect::<Result<_, _>>()
+            .map_err(LoadError::Invalid)?;
+        let map = Map::new(&self.board.map, board, obstacles).map_err(LoadError::Invalid)?;
+        //Place the circles again one by one, as they were played.
+        let mut circles: Vec<PlacedCircle> = Vec::with_capacity(self.circles.len());
+        for (idx, c) in self.circles.iter().enumerate() {
+            if ![c.x, c.y, c.radius].iter().all(|n| n.is_finite()) {
+                return invalid("a circle is not a number");
+            }
+            let circle = Circle::new(c.x, c.y, c.radius);
+            game::validate(&map, &circles, &circle, min_radius, max_radius).map_err(|err| {
+                LoadError::Invalid(format!("circle {} cannot be placed: {}", idx + 1, err))
+            })?;
+            circles.push(PlacedCircle {
+                circle,
+                owner: c.owner,
+            });
+        }
+        let mut game = Game::new(map, min_radius, max_radius, players);
+        game.set_undo_rule(self.undo_rule);
+        game.set_no_room_rule(self.no_room_rule);
+        game.set_scoring_rule(self.scoring_rule);
+        let time_control = match self.time_control.as_ref() {
+            Some(control) => Some(control.parse::<TimeControl>().map_err(LoadError::Invalid)?),
+            None => None,
+        };
+        game.set_time_control(time_control);
+        game.set_timeout_rule(self.timeout_rule);
+        game.set_ranked(self.ranked);
+        Ok(game.resume(circles, self.current_player))
+    }
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// Keeps the game in `localStorage`, so a page refresh does not lose it.
+pub fn autosave(game: &Game) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(AUTOSAVE_KEY, &Snapshot::of(game).to_json());
+    }
+}
+
+/// The game saved by `autosave`, unless it was already finished.
+pub fn load_autosave() -> Option<Game> {
+    let json = local_storage()?.get_item(AUTOSAVE_KEY).ok()??;
+    let snapshot = Snapshot::from_json(&json).ok()?;
+    snapshot.current_player?;
+    snapshot.into_game().ok()
+}
+
+/// Lets the browser save `json` as a file named `file_name`.
+pub fn download(file_name: &str, json: &str) -> Result<(), JsValue> {
+    download_text(file_name, json, "application/json")
+}
+
+/// Lets the browser save `text` of the MIME type `mime` as a file named `file_name`.
+pub fn download_text(file_name: &str, text: &str, mime: &str) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(text));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime);
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    download_blob(file_name, &blob)
+}
+
+/// Lets the browser save `blob` as a file named `file_name`.
+pub fn download_blob(file_name: &str, blob: &web_sys::Blob) -> Result<(), JsValue> {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let url = web_sys::Url::create_object_url_with_blob(blob)?;
+    let anchor = document
+        .create_element("a")?
+        .dyn_into::<web_sys::HtmlAnchorElement>()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::setup::GameSetup;
+    use crate::shape::Position2d;
+    fn played_game() -> Game {
+        let mut game = GameSetup::default().build().unwrap();
+        game.begin_circle(Position2d { x: 100.0, y: 100.0 })
+            .unwrap();
+        game.resize_circle(Position2d { x: 150.5, y: 100.0 })
+            .unwrap();
+        game.commit_circle().unwrap();
+        game.give_up().unwrap();
+        game
+    }
+    #[test]
+    fn round_trip() {
+        let game = played_game();
+        let json = Snapshot::of(&game).to_json();
+        let loaded = Snapshot::from_json(&json).unwrap().into_game().unwrap();
+        assert_eq!(loaded.circles(), game.circles());
+        assert_eq!(loaded.current_player_idx(), Some(2));
+        assert_eq!(loaded.players()[0].score() as u32, 7853);
+        assert!(!loaded.players()[1].active());
+        assert_eq!(loaded.players()[3].color(), game.players()[3].color());
+        assert_eq!(Snapshot::of(&loaded), Snapshot::of(&game));
+    }
+    #[test]
+    fn round_trip_other_boards() {
+        let mut setup = GameSetup::default();
+        setup.set_board(
+            "regular 5 (300,300) r300 a-90"
+                .parse::<GameBoard>()
+                .unwrap(),
+        );
+        let game = setup.build().unwrap();
+        let json = Snapshot::of(&game).to_json();
+        let loaded = Snapshot::from_json(&json).unwrap().into_game().unwrap();
+        assert_eq!(loaded.board(), game.board());
+        setup.set_map(Map::preset("islands").unwrap());
+        let game = setup.build().unwrap();
+        let json = Snapshot::of(&game).to_json();
+        let loaded = Snapshot::from_json(&json).unwrap().into_game().unwrap();
+        assert_eq!(loaded.map(), game.map());
+    }
+    #[test]
+    fn rejects_newer_and_broken_saves() {
+        let mut snapshot = Snapshot::of(&played_game());
+        snapshot.version = SCHEMA_VERSION + 1;
+        assert_eq!(
+            Snapshot::from_json(&snapshot.to_json()),
+            Err(LoadError::UnsupportedVersion(SCHEMA_VERSION + 1))
+        );
+        assert!(matches!(
+            Snapshot::from_json("{\"version\":1}"),
+            Err(LoadError::Json(_))
+        ));
+        //Version 1 knew only rectangles without obstacles, and still loads as one.
+        let mut snapshot = Snapshot::of(&played_game());
+        snapshot.version = 1;
+        let loaded = Snapshot::from_json(&snapshot.to_json()).unwrap();
+        assert_eq!(loaded.board.shape, None);
+        assert!(loaded.board.obstacles.is_empty());
+        let game = loaded.into_game().unwrap();
+        assert_eq!(game.board(), played_game().board());
+        let mut snapshot = Snapshot::of(&played_game());
+        snapshot.circles[0].owner = 4;
+        assert!(matches!(snapshot.into_game(), Err(LoadError::Invalid(_))));
+    }
+    #[test]
+    fn rejects_impossible_games() {
+        let broken: [fn(&mut Snapshot); 13] = [
+            |s| s.players[1].color = s.players[0].color.clone(),
+            |s| s.players[2].name = "  ".to_string(),
+            |s| s.min_radius = s.max_radius + 1.0,
+            |s| s.max_radius = f64::NAN,
+            |s| s.current_player = Some(4),
+            //The second player gave up.
+            |s| s.current_player = Some(1),
+            |s| {
+                let mut twin = s.circles[0].clone();
+                twin.x += 10.0;
+                s.circles.push(twin);
+            },
+            |s| s.circles[0].x = -10.0,
+            |s| s.board.obstacles = vec!["circle (100,100) r10".to_string()],
+            |s| s.min_radius = -1.0,
+            |s| s.board.width = 0.0,
+            |s| s.board.width = -1280.0,
+            |s| s.board.width = 1e9,
+        ];
+        for (idx, spoil) in broken.iter().enumerate() {
+            let mut snapshot = Snapshot::of(&played_game());
+            spoil(&mut snapshot);
+            let loaded = snapshot.into_game();
+            assert!(matches!(loaded, Err(LoadError::Invalid(_))), "case {}", idx);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! How circles turn into points, chosen when setting up a game.
+use crate::board::Board;
+use crate::game::PlacedCircle;
+use crate::map::Map;
+use crate::shape::{Circle, Position2d};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// A circle closer than this to the edge of the board touches it.
+const EDGE_TOUCH: f64 = 1.0;
+/// Share of its area a circle touching the edge earns on top, under `EdgeBonus`.
+const EDGE_BONUS: f64 = 0.5;
+/// About how many points of the board are looked at to measure territory.
+const TERRITORY_SAMPLES: f64 = 40_000.0;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoringRule {
+    /// The area of your circles.
+    Area,
+    /// How many circles you placed.
+    Count,
+    /// The total circumference of your circles.
+    Perimeter,
+    /// The area, with a bonus for circles touching the edge of the board.
+    EdgeBonus,
+    /// The part of the board nearer to your circles than to anyone else's.
+    Territory,
+    /// The area, but the smallest total wins.
+    Smallest,
+}
+
+impl ScoringRule {
+    pub const ALL: [ScoringRule; 6] = [
+        ScoringRule::Area,
+        ScoringRule::Count,
+        ScoringRule::Perimeter,
+        ScoringRule::EdgeBonus,
+        ScoringRule::Territory,
+        ScoringRule::Smallest,
+    ];
+    pub fn name(self) -> &'static str {
+        match self {
+            ScoringRule::Area => "area",
+            ScoringRule::Count => "count",
+            ScoringRule::Perimeter => "perimeter",
+            ScoringRule::EdgeBonus => "edge-bonus",
+            ScoringRule::Territory => "territory",
+            ScoringRule::Smallest => "smallest",
+        }
+    }
+    /// Whether the ranking goes from the lowest score up.
+    pub fn lowest_wins(self) -> bool {
+        self == ScoringRule::Smallest
+    }
+    /// The score of each of the `players`, given every circle on `map`.
+    pub fn scores(self, map: &Map, circles: &[PlacedCircle], players: usize) -> Vec<f64> {
+        let mut scores = vec![0.0; players];
+        if self == ScoringRule::Territory {
+            for (owner, area) in territory(map, circles) {
+                scores[owner] += area;
+            }
+        } else {
+            for placed in circles {
+                scores[placed.owner] += self.points(map, &placed.circle);
+            }
+        }
+        scores
+    }
+    /// What `circle` is worth on its own. Territory depends on the other circles,
+    /// so a circle is only counted by its area there.
+    pub fn points(self, map: &Map, circle: &Circle) -> f64 {
+        match self {
+            ScoringRule::Area | ScoringRule::Territory | ScoringRule::Smallest => circle.area(),
+            ScoringRule::Count => 1.0,
+            ScoringRule::Perimeter => 2.0 * PI * circle.radius(),
+            ScoringRule::EdgeBonus => {
+                let gap = map.board().clearance(circle.position()) - circle.radius();
+                if gap < EDGE_TOUCH {
+                    circle.area() * (1.0 + EDGE_BONUS)
+                } else {
+                    circle.area()
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ScoringRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ScoringRule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ScoringRule::ALL
+            .iter()
+            .copied()
+            .find(|rule| rule.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown scoring: {}", s))
+    }
+}
+
+/// Shares the free room of `map` out to the owners of the nearest circles,
+/// measured from their edges. A circle's own area always goes to its owner.
+fn territory(map: &Map, circles: &[PlacedCircle]) -> Vec<(usize, f64)> {
+    let mut shares = Vec::new();
+    if circles.is_empty() {
+        return shares;
+    }
+    let bounds = map.bounds();
+    let origin = bounds.position();
+    let cell = (bounds.width() * bounds.height() / TERRITORY_SAMPLES).sqrt();
+    let columns = (bounds.width() / cell).round().max(1.0);
+    let rows = (bounds.height() / cell).round().max(1.0);
+    let (cell_width, cell_height) = (bounds.width() / columns, bounds.height() / rows);
+    let gap = |placed: &PlacedCircle, point: &Position2d| {
+        placed.circle.position().distance(point) - placed.circle.radius()
+    };
+    for row in 0..rows as usize {
+        for column in 0..columns as usize {
+            let point = Position2d {
+                x: origin.x + (column as f64 + 0.5) * cell_width,
+                y: origin.y + (row as f64 + 0.5) * cell_height,
+            };
+            if map.clearance(point) < 0.0 {
+                continue;
+            }
+            let nearest = circles
+                .iter()
+                .min_by(|a, b| gap(a, &point).total_cmp(&gap(b, &point)))
+                .unwrap();
+            shares.push((nearest.owner, cell_width * cell_height));
+        }
+    }
+    shares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::Rect;
+    fn placed(owner: usize, x: f64, y: f64, radius: f64) -> PlacedCircle {
+        PlacedCircle {
+            circle: Circle::new(x, y, radius),
+            owner,
+        }
+    }
+    #[test]
+    fn scores_by_each_rule() {
+        let map = Map::from(Rect::new(0.0, 0.0, 400.0, 200.0));
+        //The first one touches the edge.
+        let circles = [placed(0, 50.0, 50.0, 50.0), placed(1, 200.0, 100.0, 20.0)];
+        let (big, small) = (circles[0].circle.area(), circles[1].circle.area());
+        let scores = |rule: ScoringRule| rule.scores(&map, &circles, 2);
+        assert_eq!(scores(ScoringRule::Area), vec![big, small]);
+        assert_eq!(scores(ScoringRule::Count), vec![1.0, 1.0]);
+        assert_eq!(scores(ScoringRule::Perimeter), vec![100.0 * PI, 40.0 * PI]);
+        assert_eq!(scores(ScoringRule::EdgeBonus), vec![big * 1.5, small]);
+    }
+    #[test]
+    fn territory_goes_to_the_nearest_circle() {
+        let map = Map::from(Rect::new(0.0, 0.0, 200.0, 100.0));
+        let even = [placed(0, 50.0, 50.0, 10.0), placed(1, 150.0, 50.0, 10.0)];
+        let scores = ScoringRule::Territory.scores(&map, &even, 2);
+        assert!((scores[0] - 10000.0).abs() < 100.0);
+        assert!((scores[0] + scores[1] - 20000.0).abs() < 1e-6);
+        //Room is measured from the edges, so a larger circle reaches further.
+        let uneven = [placed(0, 50.0, 50.0, 40.0), placed(1, 150.0, 50.0, 10.0)];
+        let scores = ScoringRule::Territory.scores(&map, &uneven, 2);
+        assert!((scores[0] - 11741.0).abs() < 100.0);
+        assert_eq!(ScoringRule::Territory.scores(&map, &[], 2), vec![0.0, 0.0]);
+    }
+    #[test]
+    fn parse_rule() {
+        for rule in ScoringRule::ALL {
+            assert_eq!(rule.name().parse(), Ok(rule));
+        }
+        assert!("most".parse::<ScoringRule>().is_err());
+    }
+}
@@ -0,0 +1,814 @@
+use crate::announce::Announcer;
+use crate::board::Board;
+use crate::bot::{Proposal, Strategy};
+use crate::clock::{format_time, Clock, TimeoutRule};
+use crate::game::{Game, GameError, GameEvent, PlacedCircle, Player};
+use crate::history::Move;
+use crate::i18n::{self, Message};
+use crate::keyboard::{self, Crosshair, KeyCommand};
+use crate::palette;
+use crate::replay::{Entry, Recording, Replay, ReplayControls};
+use crate::save;
+use crate::shape::{Circle, Position2d};
+use crate::viewport::{self, Viewport};
+use crate::{log, stylish_log};
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// How long a notice stays on the screen, in ms.
+const NOTICE_TIME: f64 = 4000.0;
+/// How many spots the hints point at.
+const HINT_COUNT: usize = 3;
+
+thread_local! {
+    static SESSION: RefCell<Option<Session>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` on the game in progress, if there is one.
+pub fn with_session<R>(f: impl FnOnce(&mut Session) -> R) -> Option<R> {
+    SESSION.with(|session| session.borrow_mut().as_mut().map(f))
+}
+
+/// Tears down the game in progress, if any, and starts `game` in its place.
+pub fn start(game: Game) -> Result<(), JsValue> {
+    stop();
+    let session = Session::new(game)?;
+    save::autosave(&session.game);
+    SESSION.with(|s| *s.borrow_mut() = Some(session));
+    Ok(())
+}
+
+/// Tears down the game in progress, if any.
+pub fn stop() {
+    if let Some(old) = SESSION.with(|session| session.borrow_mut().take()) {
+        old.teardown();
+    }
+}
+
+struct PlayerButton {
+    element: web_sys::HtmlButtonElement,
+    /// Whether the button was made by us, rather than found in the page.
+    created: bool,
+    _on_click: Closure<dyn FnMut()>,
+}
+
+/// A game bound to the page.
+pub struct Session {
+    game: Game,
+    buttons: Vec<PlayerButton>,
+    viewport: Viewport,
+    recording: Recording,
+    /// Set while looking back at the game instead of playing it.
+    replay: Option<(Replay, ReplayControls)>,
+    /// The strategy of each seat played by a bot.
+    bots: Vec<Option<Box<dyn Strategy>>>,
+    /// How long the current bot has been thinking, in ms.
+    thought: f64,
+    /// A message over the board, and how many ms it has left.
+    notice: Option<(Message, f64)>,
+    show_hints: bool,
+    /// The hints, and the circles they were worked out for.
+    hints: Option<(Vec<PlacedCircle>, Vec<Circle>)>,
+    /// Set if the game has a time limit.
+    clock: Option<Clock>,
+    /// Set once the keyboard is used to place circles.
+    crosshair: Option<Crosshair>,
+    /// Reads out what happens, for screen readers.
+    announcer: Announcer,
+    /// What is to be read out at the next frame.
+    announcements: Vec<String>,
+    /// Whether the screen is out of date.
+    dirty: bool,
+}
+
+impl Session {
+    fn new(game: Game) -> Result<Session, JsValue> {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let mut buttons = Vec::with_capacity(game.players().len());
+        for (idx, player) in game.players().iter().enumerate() {
+            buttons.push(bind_button(&document, idx, player)?);
+        }
+        //Hide the buttons of the classic page nobody is sitting at.
+        for color in crate::color::Color::PLAYER_COLORS.iter() {
+            if let Some(element) = document.get_element_by_id(&format!("player_{}", color.name())) {
+                if !buttons
+                    .iter()
+                    .any(|b| b.element.is_same_node(Some(&element)))
+                {
+                    element
+                        .unchecked_into::<web_sys::HtmlElement>()
+                        .set_hidden(true);
+                }
+            }
+        }
+        let canvas = document
+            .get_element_by_id("canvas")
+            .expect("Need an canvase element with id=\"canvas\".")
+            .dyn_into::<web_sys::HtmlCanvasElement>()?;
+        let (width, height, ratio) = viewport::screen_size(&canvas);
+        let viewport = Viewport::fit(game.board().bounds(), width, height, ratio);
+        let seed = js_sys::Date::now() as u64;
+        let bots = game
+            .players()
+            .iter()
+            .enumerate()
+            .map(|(idx, p)| p.bot().map(|bot| bot.kind.strategy(seed + idx as u64)))
+            .collect();
+        let clock = game.time_control().map(|control| {
+            let mut clock = Clock::new(control, game.players().len());
+            clock.start_turn(game.current_player_idx());
+            clock
+        });
+        let mut session = Session {
+            recording: Recording::new(&game),
+            game,
+            buttons,
+            viewport,
+            replay: None,
+            bots,
+            thought: 0.0,
+            notice: None,
+            show_hints: false,
+            hints: None,
+            clock,
+            crosshair: None,
+            announcer: Announcer::new(&document)?,
+            announcements: Vec::new(),
+            dirty: true,
+        };
+        for idx in 0..session.buttons.len() {
+            session.refresh_button(idx);
+        }
+        session.disable_other_players(session.game.current_player_idx());
+        if let Some(player) = session.game.current_player() {
+            let name = player.name().to_string();
+            session.announce(Message::Turn { name });
+        }
+        Ok(session)
+    }
+    fn teardown(self) {
+        self.announcer.remove();
+        if let Some((_, controls)) = self.replay.as_ref() {
+            controls.remove();
+        }
+        for button in self.buttons.iter() {
+            button.element.set_onclick(None);
+            if button.created {
+                button.element.remove();
+            } else {
+                button.element.set_hidden(true);
+            }
+        }
+    }
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+    /// The game on the screen: the replayed one while replaying, the real one otherwise.
+    pub fn shown_game(&self) -> &Game {
+        match self.replay.as_ref() {
+            Some((replay, _)) => replay.game(),
+            None => &self.game,
+        }
+    }
+    pub fn replay(&self) -> Option<&Replay> {
+        self.replay.as_ref().map(|(replay, _)| replay)
+    }
+    pub fn viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+    /// Turns the hints on or off. They stay off in ranked games.
+    pub fn set_hints(&mut self, on: bool) {
+        self.show_hints = on && !self.game.ranked();
+        self.dirty = true;
+    }
+    /// Shows the buttons and the board anew, e.g. in another language or palette.
+    pub fn refresh_all(&mut self) {
+        for idx in 0..self.buttons.len() {
+            self.refresh_button(idx);
+        }
+        self.dirty = true;
+    }
+    pub fn hints_shown(&self) -> bool {
+        self.show_hints
+    }
+    /// The largest spots left, while hints are on and a person is to move.
+    pub fn hints(&mut self) -> &[Circle] {
+        if !self.show_hints
+            || self.replay.is_some()
+            || self.game.current_player_idx().is_none()
+            || self.is_bot_turn()
+        {
+            return &[];
+        }
+        let stale = match self.hints.as_ref() {
+            Some((circles, _)) => circles.as_slice() != self.game.circles(),
+            None => true,
+        };
+        if stale {
+            let spots = self.game.largest_spots(HINT_COUNT);
+            self.hints = Some((self.game.circles().to_vec(), spots));
+        }
+        self.hints.as_ref().map_or(&[], |(_, spots)| spots)
+    }
+    /// Whether anything on the screen changed since the last call.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::replace(&mut self.dirty, false)
+    }
+    /// The message to show over the board, if any.
+    pub fn notice(&self) -> Option<&Message> {
+        match (self.replay.as_ref(), self.notice.as_ref()) {
+            (None, Some((text, _))) => Some(text),
+            _ => None,
+        }
+    }
+    /// `width` and `height` are in CSS pixels, each `pixel_ratio` device pixels wide.
+    pub fn set_screen_size(&mut self, width: f64, height: f64, pixel_ratio: f64) {
+        let bounds = self.game.board().bounds();
+        self.viewport = Viewport::fit(bounds, width, height, pixel_ratio);
+        self.dirty = true;
+    }
+    /// `center` is in screen (CSS pixel) coordinates, as are the other positions below.
+    pub fn begin_circle(&mut self, center: Position2d) {
+        if self.replay.is_some() || self.is_bot_turn() {
+            return;
+        }
+        let center = self.viewport.screen_to_board(center);
+        //The pointer takes over from the keyboard.
+        self.crosshair = None;
+        if let Err(err) = self.game.begin_circle(center) {
+            log(&err.to_string());
+        }
+        self.dirty = true;
+    }
+    pub fn resize_circle(&mut self, edge: Position2d) {
+        if self.game.draft().is_some() {
+            let edge = self.viewport.screen_to_board(edge);
+            if let Err(err) = self.game.resize_circle(edge) {
+                log(&err.to_string());
+            }
+            self.dirty = true;
+        }
+    }
+    pub fn commit_circle(&mut self) {
+        //Nothing was begun, e.g. a release that did not start on the board.
+        let circle = match self.game.draft() {
+            Some((circle, _)) => *circle,
+            None => return,
+        };
+        let player = self.game.current_player_idx();
+        let result = self.game.commit_circle();
+        self.dirty = true;
+        if let (Err(error), Some(player)) = (result.as_ref(), player) {
+            if matches!(
+                error,
+                GameError::Overlapped
+                    | GameError::OutsideBoard
+                    | GameError::OnObstacle
+                    | GameError::TooSmall
+                    | GameError::TooLarge
+            ) {
+                self.record(Entry::Rejected {
+                    player,
+                    circle,
+                    error: *error,
+                });
+                self.announce(Message::Rejected(*error));
+            }
+        }
+        let done = self.handle(result, |err| {
+            log(&err.to_string());
+            log(&format!("{:?} is invalid", circle));
+        });
+        if done {
+            self.record_last_move();
+        }
+    }
+    pub fn cancel_circle(&mut self) {
+        if let Some(circle) = self.game.cancel_circle() {
+            log(&format!("Mouse leaved while drawing {:?}", circle));
+            self.dirty = true;
+        }
+    }
+    /// The crosshair of keyboard placement, if the keyboard is in use.
+    pub fn crosshair(&self) -> Option<&Crosshair> {
+        match (self.replay.as_ref(), self.game.current_player()) {
+            (None, Some(_)) => self.crosshair.as_ref(),
+            _ => None,
+        }
+    }
+    /// Places circles by keyboard. Returns whether the key was used.
+    pub fn key(&mut self, command: KeyCommand) -> bool {
+        if self.replay.is_some() || self.is_bot_turn() {
+            return false;
+        }
+        let idx = match self.game.current_player_idx() {
+            Some(idx) => idx,
+            None => return false,
+        };
+        match command {
+            KeyCommand::Commit => {
+                if self.game.draft().is_none() {
+                    return false;
+                }
+                self.commit_circle();
+                if let Some(crosshair) = self.crosshair.as_mut() {
+                    crosshair.reset();
+                }
+            }
+            KeyCommand::Cancel => {
+                self.game.cancel_circle();
+                self.dirty = true;
+                if let Some(crosshair) = self.crosshair.as_mut() {
+                    crosshair.reset();
+                }
+            }
+            KeyCommand::FocusGiveUp => {
+                if self.buttons[idx].element.focus().is_err() {
+                    return false;
+                }
+            }
+            KeyCommand::Move(..) | KeyCommand::Grow(_) => {
+                let bounds = self.game.board().bounds();
+                let crosshair = self.crosshair.get_or_insert_with(|| {
+                    let origin = bounds.position();
+                    Crosshair::new(Position2d {
+                        x: origin.x + bounds.width() / 2.0,
+                        y: origin.y + bounds.height() / 2.0,
+                    })
+                });
+                crosshair.apply(command, keyboard::steps(), &bounds);
+                match crosshair.circle() {
+                    Some(circle) => {
+                        let center = circle.position();
+                        let edge = Position2d {
+                            x: center.x + circle.radius(),
+                            y: center.y,
+                        };
+                        let _ = self.game.begin_circle(center);
+                        let _ = self.game.resize_circle(edge);
+                    }
+                    None => {
+                        self.game.cancel_circle();
+                    }
+                }
+                self.dirty = true;
+            }
+        }
+        true
+    }
+    pub fn give_up(&mut self) {
+        if self.replay.is_some() || self.is_bot_turn() {
+            return;
+        }
+        let result = self.game.give_up();
+        if self.handle(result, |err| log(&err.to_string())) {
+            self.record_last_move();
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if self.replay.is_some() {
+            return;
+        }
+        if let Some(m) = self.game.history().moves().last() {
+            if self.game.history().can_undo() && self.game.history().rule().needs_consent() {
+                let name = self.game.players()[m.player()].name().to_string();
+                let agreed = web_sys::window()
+                    .unwrap()
+                    .confirm_with_message(&Message::UndoConsent { name }.to_string())
+                    .unwrap_or(false);
+                if !agreed {
+                    return;
+                }
+            }
+        }
+        let result = self.game.undo();
+        if self.handle(result, |err| log(&err.to_string())) {
+            self.record(Entry::Undo);
+            //Take back the moves of the bots too, as they would just play them again.
+            while self.is_bot_turn() && self.game.history().can_undo() {
+                let result = self.game.undo();
+                if self.handle(result, |err| log(&err.to_string())) {
+                    self.record(Entry::Undo);
+                }
+            }
+        }
+    }
+    pub fn redo(&mut self) {
+        if self.replay.is_some() {
+            return;
+        }
+        let result = self.game.redo();
+        if self.handle(result, |err| log(&err.to_string())) {
+            self.record(Entry::Redo);
+        }
+    }
+
+    /// Looks back at the game from its start. Playing is paused meanwhile.
+    pub fn open_replay(&mut self) -> Result<(), JsValue> {
+        if self.replay.is_some() {
+            return Ok(());
+        }
+        self.game.cancel_circle();
+        let replay = Replay::new(self.recording.clone());
+        let controls = ReplayControls::new(replay.len())?;
+        self.replay = Some((replay, controls));
+        self.after_replay_change();
+        Ok(())
+    }
+    pub fn close_replay(&mut self) {
+        if let Some((_, controls)) = self.replay.take() {
+            controls.remove();
+            for idx in 0..self.buttons.len() {
+                self.refresh_button(idx);
+            }
+            self.disable_other_players(self.game.current_player_idx());
+            self.dirty = true;
+        }
+    }
+    pub fn replay_seek(&mut self, step: usize) {
+        self.with_replay(|replay| replay.seek(step));
+    }
+    /// Moves `delta` steps forward, or back if negative.
+    pub fn replay_step(&mut self, delta: i32) {
+        self.with_replay(|replay| {
+            let step = replay.step() as i64 + delta as i64;
+            replay.seek(step.max(0) as usize)
+        });
+    }
+    pub fn replay_play(&mut self, speed: f64) {
+        self.with_replay(|replay| {
+            replay.set_speed(speed);
+            replay.play();
+        });
+    }
+    pub fn replay_pause(&mut self) {
+        self.with_replay(|replay| replay.pause());
+    }
+    pub fn replay_toggle(&mut self) {
+        self.with_replay(|replay| {
+            if replay.is_playing() {
+                replay.pause();
+            } else {
+                replay.play();
+            }
+        });
+    }
+    pub fn replay_set_speed(&mut self, speed: f64) {
+        self.with_replay(|replay| replay.set_speed(speed));
+    }
+    /// Lets `elapsed` ms pass, for autoplaying, the clock and the bots to think.
+    pub fn tick(&mut self, elapsed: f64) {
+        if let Some((_, left)) = self.notice.as_mut() {
+            *left -= elapsed;
+            if *left <= 0.0 {
+                self.notice = None;
+                self.dirty = true;
+            }
+        }
+        let changed = match self.replay.as_mut() {
+            Some((replay, _)) => replay.advance(elapsed),
+            None => {
+                self.run_clock(elapsed);
+                self.think(elapsed);
+                false
+            }
+        };
+        if changed {
+            self.after_replay_change();
+        }
+        if !self.announcements.is_empty() {
+            self.announcer.say(&self.announcements.join(" "));
+            self.announcements.clear();
+        }
+    }
+    /// Has `message` read out at the next frame, along with anything else said by then.
+    fn announce(&mut self, message: Message) {
+        self.announcements.push(message.to_string());
+    }
+    /// Shows `message` over the board for `time` ms, and reads it out.
+    fn show_notice(&mut self, message: Message, time: f64) {
+        self.announce(message.clone());
+        self.notice = Some((message, time));
+    }
+    /// Counts down the time of the player on turn, and applies the timeout rule once it is up.
+    fn run_clock(&mut self, elapsed: f64) {
+        let clock = match self.clock.as_mut() {
+            Some(clock) => clock,
+            None => return,
+        };
+        let idx = match clock.turn() {
+            Some(idx) => idx,
+            None => return,
+        };
+        let shown = format_time(clock.left(idx));
+        let timed_out = clock.tick(elapsed);
+        if format_time(clock.left(idx)) != shown {
+            self.refresh_button(idx);
+        }
+        if timed_out.is_some() {
+            self.time_out(idx);
+        }
+    }
+    fn time_out(&mut self, idx: usize) {
+        let name = self.game.players()[idx].name().to_string();
+        self.show_notice(Message::Timeout { name }, NOTICE_TIME);
+        self.game.cancel_circle();
+        self.dirty = true;
+        let clock = self.clock.as_ref().unwrap();
+        let stuck = self
+            .game
+            .players()
+            .iter()
+            .enumerate()
+            .all(|(i, p)| !p.active() || clock.bank_empty(i));
+        let rule = if stuck {
+            TimeoutRule::GiveUp
+        } else {
+            self.game.timeout_rule()
+        };
+        let result = match rule {
+            TimeoutRule::GiveUp => self.game.give_up(),
+            TimeoutRule::DropDraft if self.clock.as_mut().is_some_and(|c| c.restart_turn()) => {
+                self.refresh_button(idx);
+                return;
+            }
+            TimeoutRule::Skip | TimeoutRule::DropDraft => self.game.skip(),
+        };
+        if self.handle(result, |err| log(&err.to_string())) {
+            self.record_last_move();
+        }
+    }
+    fn is_bot_turn(&self) -> bool {
+        self.game
+            .current_player()
+            .is_some_and(|p| p.bot().is_some())
+    }
+    /// Plays the move of the bot on turn once it has thought long enough.
+    fn think(&mut self, elapsed: f64) {
+        let (idx, bot) = match self.game.current_player_idx() {
+            Some(idx) => match self.game.players()[idx].bot() {
+                Some(bot) => (idx, bot),
+                None => {
+                    self.thought = 0.0;
+                    return;
+                }
+            },
+            None => return,
+        };
+        self.thought += elapsed;
+        if self.thought < bot.think_delay {
+            return;
+        }
+        self.thought = 0.0;
+        let strategy = match self.bots[idx].as_mut() {
+            Some(strategy) => strategy,
+            None => return,
+        };
+        let circles: Vec<Circle> = self.game.circles().iter().map(|c| c.circle).collect();
+        let proposal = strategy.propose(
+            self.game.map(),
+            &circles,
+            self.game.min_radius(),
+            self.game.max_radius(),
+        );
+        let m = match proposal {
+            Proposal::Place(circle) => Move::Place {
+                player: idx,
+                circle,
+            },
+            Proposal::GiveUp => Move::GiveUp { player: idx },
+        };
+        //A bot that cannot come up with a valid move is out.
+        let result = self.game.play_move(m).or_else(|err| {
+            log(&format!("The bot proposed {:?}: {}", proposal, err));
+            self.game.give_up()
+        });
+        if self.handle(result, |err| log(&err.to_string())) {
+            self.record_last_move();
+        }
+    }
+    fn with_replay(&mut self, f: impl FnOnce(&mut Replay)) {
+        if let Some((replay, _)) = self.replay.as_mut() {
+            f(replay);
+            self.after_replay_change();
+        }
+    }
+    /// Shows the replayed scoreboard on the buttons.
+    fn after_replay_change(&mut self) {
+        self.dirty = true;
+        if let Some((replay, controls)) = self.replay.as_ref() {
+            controls.update(replay);
+            for idx in 0..self.buttons.len() {
+                self.refresh_button(idx);
+            }
+            self.disable_other_players(None);
+        }
+    }
+
+    fn record(&mut self, entry: Entry) {
+        self.recording.record(js_sys::Date::now(), entry);
+    }
+    fn record_last_move(&mut self) {
+        if let Some(m) = self.game.history().moves().last() {
+            self.record(Entry::Move(*m));
+        }
+    }
+    /// Returns whether the action went through.
+    fn handle(
+        &mut self,
+        result: Result<Vec<GameEvent>, GameError>,
+        on_error: impl FnOnce(GameError),
+    ) -> bool {
+        match result {
+            Ok(events) => {
+                self.dirty = true;
+                self.apply_events(&events);
+                save::autosave(&self.game);
+                true
+            }
+            Err(err) => {
+                on_error(err);
+                false
+            }
+        }
+    }
+    /// Reflects the outcome of a game action on the player buttons and the console.
+    fn apply_events(&mut self, events: &[GameEvent]) {
+        for event in events {
+            match event {
+                GameEvent::CirclePlaced {
+                    player,
+                    circle,
+                    score,
+                } => {
+                    log(&format!("{}", circle.area()));
+                    let name = self.game.players()[*player].name().to_string();
+                    let points = self.game.scoring_rule().points(self.game.map(), circle);
+                    self.announce(Message::Placed { name, points });
+                    //Under some scoring rules, a circle changes everyone's score.
+                    for idx in 0..self.buttons.len() {
+                        self.refresh_button(idx);
+                    }
+                    stylish_log(
+                        &format!("Player %c ★ {:.0}", score),
+                        &format!(
+                            "color:{};",
+                            palette::palette().paint(self.game.players()[*player].color())
+                        ),
+                    );
+                }
+                GameEvent::PlayerGaveUp { player, .. } => {
+                    self.refresh_button(*player);
+                    let name = self.game.players()[*player].name().to_string();
+                    self.announce(Message::GaveUp { name });
+                }
+                GameEvent::TurnSkipped { player } => {
+                    let name = self.game.players()[*player].name().to_string();
+                    let message = Message::Skipped { name };
+                    log(&message.to_string());
+                    self.announce(message);
+                }
+                GameEvent::MoveUndone { .. } => {
+                    //Players put out for lack of room may be back as well.
+                    for idx in 0..self.buttons.len() {
+                        self.refresh_button(idx);
+                    }
+                    self.notice = None;
+                }
+                GameEvent::NoRoom {
+                    player: Some(player),
+                } => {
+                    let name = self.game.players()[*player].name().to_string();
+                    self.show_notice(Message::NoRoomOut { name }, NOTICE_TIME);
+                }
+                //It stays until the game goes on again.
+                GameEvent::NoRoom { player: None } => {
+                    self.show_notice(Message::NoRoomEnd, f64::INFINITY);
+                }
+                GameEvent::TurnChanged(next) => {
+                    if let Some(crosshair) = self.crosshair.as_mut() {
+                        crosshair.reset();
+                    }
+                    if let Some(clock) = self.clock.as_mut() {
+                        clock.start_turn(*next);
+                    }
+                    self.disable_other_players(*next);
+                    //The time of the player on turn starts over in turn time.
+                    if let Some(idx) = *next {
+                        self.refresh_button(idx);
+                        let name = self.game.players()[idx].name().to_string();
+                        self.announce(Message::Turn { name });
+                    }
+                }
+                GameEvent::GameOver(ranking) => {
+                    let players = self.game.players();
+                    let mut said = Vec::with_capacity(ranking.len() + 1);
+                    if let Some(&winner) = ranking.first() {
+                        let name = players[winner].name().to_string();
+                        said.push(Message::Winner { name });
+                    }
+                    for (i, &idx) in ranking.iter().enumerate() {
+                        said.push(Message::Ranking {
+                            place: i + 1,
+                            name: players[idx].name().to_string(),
+                            score: players[idx].score(),
+                        });
+                    }
+                    for message in said {
+                        self.announce(message);
+                    }
+                }
+            }
+        }
+    }
+    /// Shows the score of the player, or that they have given up.
+    fn refresh_button(&self, idx: usize) {
+        let player = &self.shown_game().players()[idx];
+        let mut msg = if player.active() {
+            i18n::format_number(player.score(), i18n::locale())
+        } else {
+            Message::GivenUp {
+                score: player.score(),
+            }
+            .to_string()
+        };
+        if let (Some(clock), None) = (self.clock.as_ref(), self.replay.as_ref()) {
+            if player.active() && self.game.current_player_idx().is_some() {
+                msg += &format!(" ⏱ {}", format_time(clock.left(idx)));
+            }
+        }
+        let button = &self.buttons[idx];
+        set_button_text(&button.element, player, &msg);
+        //Buttons from the page keep their own look.
+        if button.created {
+            let color = palette::palette().paint(player.color()).to_string();
+            let _ = button
+                .element
+                .style()
+                .set_property("background-color", &color);
+        }
+    }
+    fn disable_other_players(&self, current_idx: Option<usize>) {
+        for button in self.buttons.iter() {
+            button.element.set_disabled(true); //Enable the current player only.
+        }
+        if let Some(idx) = current_idx {
+            //Bots give up by themselves.
+            if self.game.players()[idx].bot().is_none() {
+                self.buttons[idx].element.set_disabled(false);
+            }
+        }
+    }
+}
+
+/// Finds the button of the `idx`th player by `id="player_{idx}"`,
+/// or by the classic `id="player_{color}"`, or creates a new one.
+fn bind_button(
+    document: &web_sys::Document,
+    idx: usize,
+    player: &Player,
+) -> Result<PlayerButton, JsValue> {
+    let found = document
+        .get_element_by_id(&format!("player_{}", idx))
+        .or_else(|| document.get_element_by_id(&format!("player_{}", player.color().name())));
+    let (element, created) = match found {
+        Some(element) => (element.dyn_into::<web_sys::HtmlButtonElement>()?, false),
+        None => {
+            let element = document
+                .create_element("button")?
+                .dyn_into::<web_sys::HtmlButtonElement>()?;
+            element.set_id(&format!("player_{}", idx));
+            match document.get_element_by_id("players") {
+                Some(container) => {
+                    container.append_child(&element)?;
+                }
+                None => {
+                    let canvas = document
+                        .get_element_by_id("canvas")
+                        .expect("Need an canvase element with id=\"canvas\".");
+                    canvas
+                        .parent_node()
+                        .expect("The canvas must be in the document.")
+                        .insert_before(&element, Some(&canvas))?;
+                }
+            }
+            (element, true)
+        }
+    };
+    element.set_hidden(false);
+    let on_click = Closure::wrap(Box::new(move || {
+        with_session(|session| session.give_up());
+    }) as Box<dyn FnMut()>);
+    element.set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    Ok(PlayerButton {
+        element,
+        created,
+        _on_click: on_click,
+    })
+}
+
+fn set_button_text(button: &web_sys::HtmlButtonElement, player: &Player, msg: &str) {
+    button.set_inner_text(&format!("{}: {}", player.name(), msg));
+}
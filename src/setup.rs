@@ -0,0 +1,305 @@
+use crate::board::GameBoard;
+use crate::bot::{Bot, BotKind};
+use crate::clock::{TimeControl, TimeoutRule};
+use crate::color::Color;
+use crate::game::{Game, NoRoomRule, Player};
+use crate::history::UndoRule;
+use crate::map::Map;
+use crate::scoring::ScoringRule;
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetupError {
+    TooFewPlayers,
+    TooManyPlayers,
+    EmptyName,
+    UnknownColor(String),
+    DuplicateColor(Color),
+    /// Not numbers, below zero, or the minimum above the maximum.
+    BadRadiusLimits,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SetupError::TooFewPlayers => write!(f, "At least {} players are needed.", MIN_PLAYERS),
+            SetupError::TooManyPlayers => write!(f, "At most {} players can play.", MAX_PLAYERS),
+            SetupError::EmptyName => write!(f, "A player name cannot be empty."),
+            SetupError::UnknownColor(msg) => write!(f, "{}", msg),
+            SetupError::DuplicateColor(color) => {
+                write!(f, "{} is already taken by another player.", color.name())
+            }
+            SetupError::BadRadiusLimits => {
+                write!(f, "The radius limits must be numbers with 0 <= min <= max.")
+            }
+        }
+    }
+}
+
+/// Whether circles of radius `min..=max` make a playable game.
+pub fn check_radius_limits(min: f64, max: f64) -> Result<(), SetupError> {
+    if min.is_finite() && max.is_finite() && min >= 0.0 && min <= max {
+        Ok(())
+    } else {
+        Err(SetupError::BadRadiusLimits)
+    }
+}
+
+impl From<SetupError> for JsValue {
+    fn from(err: SetupError) -> JsValue {
+        JsValue::from_str(&err.to_string())
+    }
+}
+
+/// Everything needed to start a match.
+///
+/// From JS:
+/// ```js
+/// const setup = new GameSetup();
+/// setup.add_player("Alice", "red");
+/// setup.add_player("Bob", "#4aa3e3");
+/// start_game(setup);
+/// ```
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct GameSetup {
+    players: Vec<Player>,
+    map: Map,
+    min_radius: f64,
+    max_radius: f64,
+    undo_rule: UndoRule,
+    no_room_rule: NoRoomRule,
+    scoring_rule: ScoringRule,
+    time_control: Option<TimeControl>,
+    timeout_rule: TimeoutRule,
+    ranked: bool,
+}
+
+impl Default for GameSetup {
+    /// The classic four players game.
+    fn default() -> Self {
+        let mut setup = GameSetup::new();
+        for (name, color) in ["R", "G", "B", "Y"].iter().zip(Color::PLAYER_COLORS) {
+            setup.push_player(name, color).unwrap();
+        }
+        setup
+    }
+}
+
+impl GameSetup {
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+    pub fn push_player(&mut self, name: &str, color: Color) -> Result<(), SetupError> {
+        self.push_seat(name, color, None)
+    }
+    /// Seats a computer player.
+    pub fn push_bot(&mut self, name: &str, color: Color, bot: Bot) -> Result<(), SetupError> {
+        self.push_seat(name, color, Some(bot))
+    }
+    fn push_seat(&mut self, name: &str, color: Color, bot: Option<Bot>) -> Result<(), SetupError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(SetupError::EmptyName);
+        }
+        if self.players.len() >= MAX_PLAYERS {
+            return Err(SetupError::TooManyPlayers);
+        }
+        if self.players.iter().any(|p| p.color() == color) {
+            return Err(SetupError::DuplicateColor(color));
+        }
+        self.players.push(Player::new(name, color).with_bot(bot));
+        Ok(())
+    }
+    pub fn radius_limits(&self) -> (f64, f64) {
+        (self.min_radius, self.max_radius)
+    }
+    /// Plays on `board`, without obstacles.
+    pub fn set_board(&mut self, board: impl Into<GameBoard>) {
+        self.map = Map::from(board.into());
+    }
+    pub fn set_map(&mut self, map: Map) {
+        self.map = map;
+    }
+    pub fn set_time_control(&mut self, control: Option<TimeControl>) {
+        self.time_control = control;
+    }
+    pub fn build(&self) -> Result<Game, SetupError> {
+        if self.players.len() < MIN_PLAYERS {
+            return Err(SetupError::TooFewPlayers);
+        }
+        check_radius_limits(self.min_radius, self.max_radius)?;
+        let mut game = Game::new(
+            self.map.clone(),
+            self.min_radius,
+            self.max_radius,
+            self.players.clone(),
+        );
+        game.set_undo_rule(self.undo_rule);
+        game.set_no_room_rule(self.no_room_rule);
+        game.set_scoring_rule(self.scoring_rule);
+        game.set_time_control(self.time_control);
+        game.set_timeout_rule(self.timeout_rule);
+        game.set_ranked(self.ranked);
+        Ok(game)
+    }
+}
+
+#[wasm_bindgen]
+impl GameSetup {
+    /// An empty setup on the standard 1280x720 board.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> GameSetup {
+        GameSetup {
+            players: Vec::with_capacity(MAX_PLAYERS),
+            map: Map::default(),
+            min_radius: 18.0,
+            max_radius: 360.0,
+            undo_rule: UndoRule::Unlimited,
+            no_room_rule: NoRoomRule::EndGame,
+            scoring_rule: ScoringRule::Area,
+            time_control: None,
+            timeout_rule: TimeoutRule::Skip,
+            ranked: false,
+        }
+    }
+    /// Seats a player. `color` is a color name such as `"red"` or its hex code.
+    pub fn add_player(&mut self, name: &str, color: &str) -> Result<(), JsValue> {
+        let color = color.parse().map_err(SetupError::UnknownColor)?;
+        Ok(self.push_player(name, color)?)
+    }
+    /// Seats a computer player, which waits `think_delay` ms before each move.
+    pub fn add_bot(
+        &mut self,
+        name: &str,
+        color: &str,
+        kind: BotKind,
+        think_delay: f64,
+    ) -> Result<(), JsValue> {
+        let color = color.parse().map_err(SetupError::UnknownColor)?;
+        let bot = Bot {
+            kind,
+            think_delay: think_delay.max(0.0),
+        };
+        Ok(self.push_bot(name, color, bot)?)
+    }
+    /// Plays on another shape of board, written as in the notation,
+    /// e.g. `"circle (360,360) r360"` or `"regular 6 (400,400) r400"`.
+    pub fn set_board_shape(&mut self, shape: &str) -> Result<(), JsValue> {
+        let board = shape
+            .parse::<GameBoard>()
+            .map_err(|e| JsValue::from_str(&e))?;
+        self.set_board(board);
+        Ok(())
+    }
+    /// Plays on one of the built-in maps, named as in `map_names()`.
+    pub fn use_map(&mut self, name: &str) -> Result<(), JsValue> {
+        let map = Map::preset(name)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown map: {}", name)))?;
+        self.set_map(map);
+        Ok(())
+    }
+    /// Plays on a map written in JSON, as described in the `map` module.
+    pub fn load_map(&mut self, json: &str) -> Result<(), JsValue> {
+        let map = Map::from_json(json).map_err(|e| JsValue::from_str(&e))?;
+        self.set_map(map);
+        Ok(())
+    }
+    /// Limits the radius of every circle to `min..=max`.
+    pub fn set_radius_limits(&mut self, min: f64, max: f64) {
+        self.min_radius = min;
+        self.max_radius = max;
+    }
+    pub fn set_undo_rule(&mut self, rule: UndoRule) {
+        self.undo_rule = rule;
+    }
+    /// What happens once no circle of the minimum radius fits any more.
+    pub fn set_no_room_rule(&mut self, rule: NoRoomRule) {
+        self.no_room_rule = rule;
+    }
+    pub fn set_scoring_rule(&mut self, rule: ScoringRule) {
+        self.scoring_rule = rule;
+    }
+    /// Gives every turn `ms` of time. Zero lifts the limit.
+    pub fn set_turn_time(&mut self, ms: f64) {
+        self.set_time_control((ms > 0.0).then_some(TimeControl::PerTurn(ms)));
+    }
+    /// Gives each player `ms` of time for the whole game, as with a chess clock.
+    /// Zero lifts the limit.
+    pub fn set_time_bank(&mut self, ms: f64) {
+        self.set_time_control((ms > 0.0).then_some(TimeControl::Bank(ms)));
+    }
+    pub fn set_timeout_rule(&mut self, rule: TimeoutRule) {
+        self.timeout_rule = rule;
+    }
+    /// Competitive play: hints are not available.
+    pub fn set_ranked(&mut self, ranked: bool) {
+        self.ranked = ranked;
+    }
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn player_count_limits() {
+        let mut setup = GameSetup::new();
+        setup.push_player("Alice", Color::RED).unwrap();
+        assert_eq!(setup.build().err(), Some(SetupError::TooFewPlayers));
+        for color in Color::PLAYER_COLORS.iter().skip(1) {
+            setup.push_player("Bob", *color).unwrap();
+        }
+        assert_eq!(setup.build().unwrap().players().len(), MAX_PLAYERS);
+        assert_eq!(
+            setup.push_player("Carol", Color::LIGHT_RED),
+            Err(SetupError::TooManyPlayers)
+        );
+    }
+    #[test]
+    fn rejects_bad_players() {
+        let mut setup = GameSetup::default();
+        assert_eq!(
+            setup.push_player("  ", Color::PINK),
+            Err(SetupError::EmptyName)
+        );
+        assert_eq!(
+            setup.push_player("Dave", Color::BLUE),
+            Err(SetupError::DuplicateColor(Color::BLUE))
+        );
+        setup.push_player(" Dave ", Color::PINK).unwrap();
+        assert_eq!(setup.players()[4].name(), "Dave");
+    }
+    #[test]
+    fn rejects_bad_radius_limits() {
+        let mut setup = GameSetup::default();
+        for (min, max) in [
+            (f64::NAN, 360.0),
+            (18.0, f64::INFINITY),
+            (-1.0, 10.0),
+            (100.0, 10.0),
+        ] {
+            setup.set_radius_limits(min, max);
+            assert_eq!(setup.build().err(), Some(SetupError::BadRadiusLimits));
+        }
+        setup.set_radius_limits(10.0, 10.0);
+        assert!(setup.build().is_ok());
+    }
+    #[test]
+    fn bots_take_seats() {
+        let mut setup = GameSetup::new();
+        setup.push_player("Alice", Color::RED).unwrap();
+        setup
+            .push_bot("CPU", Color::BLUE, Bot::new(BotKind::Greedy))
+            .unwrap();
+        let game = setup.build().unwrap();
+        assert_eq!(game.players()[0].bot(), None);
+        assert_eq!(game.players()[1].bot().unwrap().kind, BotKind::Greedy);
+    }
+}
@@ -1,5 +1,14 @@
-use std::convert::TryInto;
-#[derive(Default, Debug, Clone, Copy)]
+use crate::board::Board;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// The solver below finds the largest empty circle to within this many units of radius.
+pub const EMPTY_CIRCLE_TOLERANCE: f64 = 0.5;
+/// The most squares the solver starts with along the longer side of a board,
+/// so that long thin boards do not start it with a huge queue.
+const SEED_CELLS: f64 = 64.0;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct Position2d {
     pub x: f64,
     pub y: f64,
@@ -7,11 +16,11 @@ pub struct Position2d {
 
 impl Position2d {
     pub fn distance(&self, other: &Position2d) -> f64 {
-        f64::from((self.x - other.x).powf(2.0) + (self.y - other.y).powf(2.0)).sqrt()
+        ((self.x - other.x).powf(2.0) + (self.y - other.y).powf(2.0)).sqrt()
     }
 }
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct Circle {
     position: Position2d,
     radius: f64,
@@ -22,7 +31,7 @@ impl Circle {
     //     self.position.distance(point) <= f64::from(self.radius)
     // }
     pub fn is_overlapped(&self, other: &Circle) -> bool {
-        self.position.distance(&other.position) <= f64::from(self.radius + other.radius)
+        self.position.distance(&other.position) <= self.radius + other.radius
     }
     pub fn new(x: f64, y: f64, radius: f64) -> Self {
         Circle {
@@ -47,7 +56,7 @@ impl Circle {
     }
 }
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct Rect {
     position: Position2d,
     width: f64,
@@ -62,22 +71,27 @@ impl Rect {
     pub fn is_outside(&self, other: &Circle) -> bool {
         let (left, right, top, bottom): (f64, f64, f64, f64) = (
             self.position.x,
-            self.position.x + self.width as f64,
+            self.position.x + self.width,
             self.position.y,
-            self.position.y + self.height as f64,
+            self.position.y + self.height,
         );
         let center = other.position();
-        match (center.x - left)
+        let margin = (center.x - left)
             .min(right - center.x)
             .min(center.y - top)
-            .min(bottom - center.y)
-            .try_into()
-        {
-            Ok(value) => other.radius() > value,
-            Err(_) => false,
-        }
+            .min(bottom - center.y);
+        other.radius() > margin
     }
 
+    pub fn position(&self) -> Position2d {
+        self.position
+    }
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+    pub fn height(&self) -> f64 {
+        self.height
+    }
     pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
         Rect {
             position: Position2d { x, y },
@@ -87,6 +101,151 @@ impl Rect {
     }
 }
 
+/// How far `center` is from the edge of `board` or the nearest of `circles`.
+/// Negative if it is outside the board or inside a circle.
+pub fn clearance(board: &dyn Board, circles: &[Circle], center: Position2d) -> f64 {
+    circles.iter().fold(board.clearance(center), |room, c| {
+        room.min(center.distance(&c.position) - c.radius)
+    })
+}
+
+/// A square part of the search space, ordered by the best clearance it may hold.
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    center: Position2d,
+    half: f64,
+    clearance: f64,
+    bound: f64,
+}
+
+impl Cell {
+    fn new(
+        center: Position2d,
+        half: f64,
+        board: &dyn Board,
+        circles: &[Circle],
+        max_radius: f64,
+    ) -> Cell {
+        let clearance = clearance(board, circles, center).min(max_radius);
+        //Clearance changes no faster than the distance moved, so nothing in the cell
+        //can be further from everything than the center plus half the diagonal.
+        let bound = (clearance + half * std::f64::consts::SQRT_2).min(max_radius);
+        Cell {
+            center,
+            half,
+            clearance,
+            bound,
+        }
+    }
+}
+
+impl PartialEq for Cell {
+    fn eq(&self, other: &Cell) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Cell {}
+
+impl PartialOrd for Cell {
+    fn partial_cmp(&self, other: &Cell) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cell {
+    fn cmp(&self, other: &Cell) -> Ordering {
+        self.bound.total_cmp(&other.bound)
+    }
+}
+
+/// The largest circle, no larger than `max_radius`, that fits inside `board` without
+/// overlapping any of `circles`, or `None` if there is no room at all.
+///
+/// Found by branch and bound over ever smaller squares of the board.
+/// The radius is the exact room around the returned center, so the circle is empty,
+/// and it is at most `EMPTY_CIRCLE_TOLERANCE` short of the true maximum.
+/// It touches its nearest neighbour; shrink it a bit before placing it.
+pub fn largest_empty_circle(
+    board: &dyn Board,
+    circles: &[Circle],
+    max_radius: f64,
+) -> Option<Circle> {
+    let bounds = board.bounds();
+    let (short, long) = (
+        bounds.width.min(bounds.height),
+        bounds.width.max(bounds.height),
+    );
+    if !short.is_finite() || !long.is_finite() || short <= 0.0 {
+        return None;
+    }
+    let size = short.max(long / SEED_CELLS);
+    let mut queue = BinaryHeap::new();
+    let mut y = bounds.position.y;
+    while y < bounds.position.y + bounds.height {
+        let mut x = bounds.position.x;
+        while x < bounds.position.x + bounds.width {
+            let center = Position2d {
+                x: x + size / 2.0,
+                y: y + size / 2.0,
+            };
+            queue.push(Cell::new(center, size / 2.0, board, circles, max_radius));
+            x += size;
+        }
+        y += size;
+    }
+    let mut best = *queue
+        .iter()
+        .max_by(|a, b| a.clearance.total_cmp(&b.clearance))?;
+    while let Some(cell) = queue.pop() {
+        if cell.bound - best.clearance <= EMPTY_CIRCLE_TOLERANCE {
+            break;
+        }
+        let half = cell.half / 2.0;
+        for (dx, dy) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+            let center = Position2d {
+                x: cell.center.x + dx * half,
+                y: cell.center.y + dy * half,
+            };
+            let child = Cell::new(center, half, board, circles, max_radius);
+            if child.clearance > best.clearance {
+                best = child;
+            }
+            if child.bound - best.clearance > EMPTY_CIRCLE_TOLERANCE {
+                queue.push(child);
+            }
+        }
+    }
+    if best.clearance > 0.0 {
+        Some(Circle::new(best.center.x, best.center.y, best.clearance))
+    } else {
+        None
+    }
+}
+
+/// Up to `k` candidates, largest first: the largest empty circle,
+/// then the largest one left if that were taken, and so on.
+/// So the candidates never overlap each other.
+pub fn largest_empty_circles(
+    board: &dyn Board,
+    circles: &[Circle],
+    max_radius: f64,
+    k: usize,
+) -> Vec<Circle> {
+    let mut taken = circles.to_vec();
+    let mut found = Vec::with_capacity(k);
+    while found.len() < k {
+        match largest_empty_circle(board, &taken, max_radius) {
+            Some(circle) => {
+                taken.push(circle);
+                found.push(circle);
+            }
+            None => break,
+        }
+    }
+    found
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,10 +294,124 @@ mod tests {
         assert!(board.is_outside(&Circle::new(x, y + h, one)));
         assert!(!board.is_outside(&Circle::new(x + one, y + h - one, one)));
         assert!(board.is_outside(&Circle::new(x + w, y, one)));
-        assert!(!board.is_outside(&Circle::new(x + w - one, y - one, one)));
+        //Just inside the top right corner. This used to test `y - one`,
+        //which is above the board and so could never be inside.
+        assert!(!board.is_outside(&Circle::new(x + w - one, y + one, one)));
         assert!(board.is_outside(&Circle::new(x + w, y + h, one)));
         assert!(!board.is_outside(&Circle::new(x + w - one, y + h - one, one)));
         //arbitary
         assert!(board.is_outside(&Circle::new(x + w - 32.0, y + h - 31.0, 32.0)));
     }
+    fn random_circles(rng: &mut crate::bot::Rng, board: &Rect, count: usize) -> Vec<Circle> {
+        (0..count)
+            .map(|_| {
+                Circle::new(
+                    rng.below(board.width() as u64) as f64,
+                    rng.below(board.height() as u64) as f64,
+                    1.0 + rng.below(120) as f64,
+                )
+            })
+            .collect()
+    }
+    /// The best clearance among a fine grid and random points.
+    fn sampled_best(rng: &mut crate::bot::Rng, board: &Rect, circles: &[Circle], step: f64) -> f64 {
+        let mut best = f64::NEG_INFINITY;
+        let mut y = 0.0;
+        while y <= board.height() {
+            let mut x = 0.0;
+            while x <= board.width() {
+                best = best.max(clearance(board, circles, Position2d { x, y }));
+                x += step;
+            }
+            y += step;
+        }
+        for _ in 0..2000 {
+            let point = Position2d {
+                x: rng.below(board.width() as u64 * 100) as f64 / 100.0,
+                y: rng.below(board.height() as u64 * 100) as f64 / 100.0,
+            };
+            best = best.max(clearance(board, circles, point));
+        }
+        best
+    }
+    #[test]
+    fn largest_empty_circle_of_an_empty_board() {
+        let board = Rect::new(0.0, 0.0, 1280.0, 720.0);
+        let circle = largest_empty_circle(&board, &[], f64::INFINITY).unwrap();
+        assert!((360.0 - circle.radius()).abs() <= EMPTY_CIRCLE_TOLERANCE);
+        let capped = largest_empty_circle(&board, &[], 100.0).unwrap();
+        assert_eq!(capped.radius(), 100.0);
+        let full = [Circle::new(640.0, 360.0, 740.0)];
+        assert_eq!(largest_empty_circle(&board, &full, f64::INFINITY), None);
+    }
+    #[test]
+    fn largest_empty_circle_of_thin_or_broken_boards() {
+        //Starts with at most 64 squares however long the board is.
+        let strip = Rect::new(0.0, 0.0, 100000.0, 1000.0);
+        let found = largest_empty_circle(&strip, &[], f64::INFINITY).unwrap();
+        assert!((500.0 - found.radius()).abs() <= EMPTY_CIRCLE_TOLERANCE);
+        for broken in [
+            Rect::new(0.0, 0.0, 0.0, 720.0),
+            Rect::new(0.0, 0.0, -10.0, 720.0),
+            Rect::new(0.0, 0.0, f64::NAN, 720.0),
+            Rect::new(0.0, 0.0, f64::INFINITY, 720.0),
+        ] {
+            assert_eq!(largest_empty_circle(&broken, &[], f64::INFINITY), None);
+        }
+    }
+    #[test]
+    fn largest_empty_circle_against_sampling() {
+        let mut rng = crate::bot::Rng::new(42);
+        let board = Rect::new(0.0, 0.0, 640.0, 480.0);
+        let step = 4.0;
+        for round in 0..30 {
+            let circles = random_circles(&mut rng, &board, round % 12);
+            let sampled = sampled_best(&mut rng, &board, &circles, step);
+            match largest_empty_circle(&board, &circles, f64::INFINITY) {
+                Some(found) => {
+                    //The circle really is empty.
+                    let room = clearance(&board, &circles, found.position());
+                    assert!(found.radius() <= room + 1e-9, "{:?} is not empty", found);
+                    //Nothing sampled beats it by more than the tolerance,
+                    assert!(found.radius() >= sampled - EMPTY_CIRCLE_TOLERANCE);
+                    //and the grid comes within half its diagonal of the true maximum.
+                    assert!(found.radius() <= sampled + step / std::f64::consts::SQRT_2 + 1e-9);
+                }
+                None => assert!(sampled <= EMPTY_CIRCLE_TOLERANCE),
+            }
+        }
+    }
+    #[test]
+    fn largest_empty_circle_in_other_shapes() {
+        use crate::board::GameBoard;
+        let arena: GameBoard = "circle (100,100) r100".parse().unwrap();
+        let found = largest_empty_circle(&arena, &[], f64::INFINITY).unwrap();
+        assert!(100.0 - found.radius() <= EMPTY_CIRCLE_TOLERANCE);
+        let l: GameBoard = "polygon (0,0) (300,0) (300,100) (100,100) (100,300) (0,300)"
+            .parse()
+            .unwrap();
+        let found = largest_empty_circle(&l, &[], f64::INFINITY).unwrap();
+        assert!(l.contains(&found));
+        //The corner of the L holds a bit more than the arms.
+        assert!(found.radius() > 50.0 && found.radius() < 71.0);
+    }
+    #[test]
+    fn top_k_candidates() {
+        let mut rng = crate::bot::Rng::new(7);
+        let board = Rect::new(0.0, 0.0, 640.0, 480.0);
+        let circles = random_circles(&mut rng, &board, 6);
+        let found = largest_empty_circles(&board, &circles, 150.0, 5);
+        assert_eq!(found.len(), 5);
+        assert_eq!(
+            Some(found[0]),
+            largest_empty_circle(&board, &circles, 150.0)
+        );
+        for (i, a) in found.iter().enumerate() {
+            assert!(clearance(&board, &circles, a.position()) >= a.radius() - 1e-9);
+            for b in found.iter().skip(i + 1) {
+                assert!(a.position().distance(&b.position()) >= a.radius() + b.radius() - 1e-9);
+                assert!(b.radius() <= a.radius() + EMPTY_CIRCLE_TOLERANCE);
+            }
+        }
+    }
 }
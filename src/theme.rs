@@ -0,0 +1,240 @@
+//! How the board and the text over it look: colors, lines and fonts.
+//!
+//! Themes are read from JSON, with colors written as CSS colors.
+//! Fields left out are taken from the light theme:
+//!
+//! ```json
+//! {
+//!   "name": "night",
+//!   "letterbox": "#000000",
+//!   "background": "#101820",
+//!   "grid": "rgba(255, 255, 255, 0.1)",
+//!   "font": "Georgia, serif"
+//! }
+//! ```
+use crate::color::Color;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+
+/// How the marks of the player on turn, e.g. hints, are drawn from their color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Shade {
+    /// Darker, for light boards.
+    Dark,
+    /// Lighter, for dark boards.
+    Light,
+}
+
+impl Shade {
+    pub fn apply(self, color: Color) -> Color {
+        match self {
+            Shade::Dark => color.to_dark(),
+            Shade::Light => color.to_light(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub name: String,
+    /// Around the board, where the screen is of another shape.
+    pub letterbox: Color,
+    /// The play area.
+    pub background: Color,
+    /// Lines across the play area every `grid_spacing` board units, if any.
+    pub grid: Option<Color>,
+    pub grid_spacing: f64,
+    /// The edge of the board and of the obstacles.
+    pub outline: Color,
+    pub obstacle: Color,
+    /// The CSS font family of all text.
+    pub font: String,
+    pub highlight: Shade,
+    /// The band behind notices, and the text on it.
+    pub notice: Color,
+    pub notice_text: Color,
+    /// Laid over the board once the game is over, if any.
+    pub ranking_backdrop: Option<Color>,
+    /// The edge around the names of the ranking, which are in the colors of the players.
+    pub ranking_outline: Color,
+    /// The line under the ranking.
+    pub ranking_text: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::light()
+    }
+}
+
+impl Theme {
+    pub const PRESETS: [&'static str; 4] = ["light", "dark", "paper", "chalkboard"];
+    pub fn preset(name: &str) -> Option<Theme> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "light" => Some(Theme::light()),
+            "dark" => Some(Theme::dark()),
+            "paper" => Some(Theme::paper()),
+            "chalkboard" => Some(Theme::chalkboard()),
+            _ => None,
+        }
+    }
+    pub fn from_json(json: &str) -> Result<Theme, String> {
+        let theme: Theme = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if !theme.grid_spacing.is_finite() || theme.grid_spacing <= 0.0 {
+            return Err(format!("Bad grid spacing: {}", theme.grid_spacing));
+        }
+        Ok(theme)
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("A theme is always serializable.")
+    }
+    /// A CSS font of `size` pixels in the family of the theme.
+    pub fn font(&self, size: f64) -> String {
+        format!("{}px {}", size, self.font)
+    }
+    fn light() -> Theme {
+        Theme {
+            name: "light".to_string(),
+            letterbox: Color::from_hex(0xeeeeee),
+            background: Color::WHITE,
+            grid: None,
+            grid_spacing: 40.0,
+            outline: Color::from_hex(0x808080),
+            obstacle: Color::from_hex(0xb0b0b0),
+            font: "system-ui".to_string(),
+            highlight: Shade::Dark,
+            notice: Color::BLACK.with_alpha(0.6),
+            notice_text: Color::WHITE,
+            ranking_backdrop: None,
+            ranking_outline: Color::BLACK,
+            ranking_text: Color::BLACK,
+        }
+    }
+    fn dark() -> Theme {
+        Theme {
+            name: "dark".to_string(),
+            letterbox: Color::from_hex(0x121212),
+            background: Color::from_hex(0x1e1e1e),
+            grid: None,
+            grid_spacing: 40.0,
+            outline: Color::from_hex(0x5c5c5c),
+            obstacle: Color::from_hex(0x3a3a3a),
+            font: "system-ui".to_string(),
+            highlight: Shade::Light,
+            notice: Color::WHITE.with_alpha(0.15),
+            notice_text: Color::from_hex(0xf0f0f0),
+            ranking_backdrop: Some(Color::BLACK.with_alpha(0.4)),
+            ranking_outline: Color::BLACK,
+            ranking_text: Color::from_hex(0xe0e0e0),
+        }
+    }
+    fn paper() -> Theme {
+        Theme {
+            name: "paper".to_string(),
+            letterbox: Color::from_hex(0xd9cfbd),
+            background: Color::from_hex(0xf7f1e3),
+            grid: Some(Color::from_hex(0xd3e0ea)),
+            grid_spacing: 40.0,
+            outline: Color::from_hex(0x8b7d6b),
+            obstacle: Color::from_hex(0xcdbfa6),
+            font: "Georgia, serif".to_string(),
+            highlight: Shade::Dark,
+            notice: Color::from_hex(0x3b3024).with_alpha(0.8),
+            notice_text: Color::from_hex(0xf7f1e3),
+            ranking_backdrop: None,
+            ranking_outline: Color::from_hex(0x3b3024),
+            ranking_text: Color::from_hex(0x3b3024),
+        }
+    }
+    fn chalkboard() -> Theme {
+        Theme {
+            name: "chalkboard".to_string(),
+            letterbox: Color::from_hex(0x5a3d24),
+            background: Color::from_hex(0x2f4f3a),
+            grid: Some(Color::WHITE.with_alpha(0.08)),
+            grid_spacing: 80.0,
+            outline: Color::from_hex(0xe8e8e0),
+            obstacle: Color::from_hex(0x56705e),
+            font: "'Chalkboard SE', 'Comic Sans MS', cursive".to_string(),
+            highlight: Shade::Light,
+            notice: Color::BLACK.with_alpha(0.45),
+            notice_text: Color::from_hex(0xf4f4ec),
+            ranking_backdrop: Some(Color::from_hex(0x2f4f3a).with_alpha(0.6)),
+            ranking_outline: Color::from_hex(0x1c3024),
+            ranking_text: Color::from_hex(0xf4f4ec),
+        }
+    }
+}
+
+thread_local! {
+    static THEME: RefCell<Theme> = RefCell::new(Theme::light());
+    /// Whether the theme still follows the light or dark scheme of the system.
+    static FOLLOW_SYSTEM: Cell<bool> = const { Cell::new(true) };
+}
+
+pub fn theme() -> Theme {
+    THEME.with(|t| t.borrow().clone())
+}
+
+/// Uses `theme` from now on, rather than the one of the system scheme.
+pub fn set_theme(theme: Theme) {
+    FOLLOW_SYSTEM.with(|f| f.set(false));
+    THEME.with(|t| *t.borrow_mut() = theme);
+}
+
+pub fn follows_system() -> bool {
+    FOLLOW_SYSTEM.with(Cell::get)
+}
+
+/// Goes back to the light or dark theme, as the system scheme is `dark` or not.
+pub fn follow_system(dark: bool) {
+    let name = if dark { "dark" } else { "light" };
+    THEME.with(|t| *t.borrow_mut() = Theme::preset(name).unwrap());
+    FOLLOW_SYSTEM.with(|f| f.set(true));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn presets() {
+        for name in Theme::PRESETS {
+            let theme = Theme::preset(name).unwrap();
+            assert_eq!(theme.name, name);
+            assert_eq!(Theme::from_json(&theme.to_json()), Ok(theme));
+        }
+        assert_eq!(
+            Theme::preset(" Dark ").map(|t| t.highlight),
+            Some(Shade::Light)
+        );
+        assert_eq!(Theme::preset("neon"), None);
+        assert_eq!(Theme::default().font(30.0), "30px system-ui");
+    }
+    #[test]
+    fn partial_json() {
+        let theme = Theme::from_json(
+            r#"{"name": "night", "background": "hsl(210, 30%, 10%)", "grid": "rgba(255, 255, 255, 0.1)", "highlight": "light"}"#,
+        )
+        .unwrap();
+        assert_eq!(theme.name, "night");
+        assert_eq!(theme.background.to_string(), "#121a21");
+        assert_eq!(theme.grid.map(|g| g.alpha() < 0.2), Some(true));
+        assert_eq!(theme.highlight, Shade::Light);
+        assert_eq!(theme.outline, Theme::light().outline);
+        assert!(Theme::from_json(r#"{"background": "shiny"}"#).is_err());
+        assert!(Theme::from_json(r#"{"grid_spacing": 0}"#).is_err());
+        assert!(theme.to_json().contains(r##""background":"#121a21""##));
+    }
+    #[test]
+    fn following_the_system() {
+        follow_system(true);
+        assert_eq!(theme().name, "dark");
+        set_theme(Theme::preset("paper").unwrap());
+        assert!(!follows_system());
+        follow_system(false);
+        assert!(follows_system());
+        assert_eq!(theme().name, "light");
+    }
+}
@@ -0,0 +1,235 @@
+use crate::board::Board;
+use crate::renderer::CanvasRenderer;
+use crate::session::with_session;
+use crate::shape::{Position2d, Rect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Fits the bounds of the logical board into a screen of any size,
+/// keeping its aspect ratio and centering it with letterboxing.
+///
+/// The screen is measured in CSS pixels, while the canvas holds `pixel_ratio`
+/// device pixels for each of them so that circles stay sharp on dense displays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    board: Rect,
+    screen_width: f64,
+    screen_height: f64,
+    pixel_ratio: f64,
+    scale: f64,
+    offset: Position2d,
+}
+
+impl Viewport {
+    pub fn fit(board: Rect, screen_width: f64, screen_height: f64, pixel_ratio: f64) -> Viewport {
+        let scale = (screen_width / board.width()).min(screen_height / board.height());
+        let scale = if scale.is_finite() && scale > 0.0 {
+            scale
+        } else {
+            1.0
+        };
+        Viewport {
+            board,
+            screen_width,
+            screen_height,
+            pixel_ratio,
+            scale,
+            offset: Position2d {
+                x: (screen_width - board.width() * scale) / 2.0,
+                y: (screen_height - board.height() * scale) / 2.0,
+            },
+        }
+    }
+    pub fn board(&self) -> &Rect {
+        &self.board
+    }
+    pub fn screen_width(&self) -> f64 {
+        self.screen_width
+    }
+    pub fn screen_height(&self) -> f64 {
+        self.screen_height
+    }
+    /// Device pixels per CSS pixel.
+    pub fn pixel_ratio(&self) -> f64 {
+        self.pixel_ratio
+    }
+    /// CSS pixels per board unit.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+    /// Where the board origin lands on the screen.
+    pub fn offset(&self) -> Position2d {
+        self.offset
+    }
+    pub fn screen_to_board(&self, screen: Position2d) -> Position2d {
+        let origin = self.board.position();
+        Position2d {
+            x: (screen.x - self.offset.x) / self.scale + origin.x,
+            y: (screen.y - self.offset.y) / self.scale + origin.y,
+        }
+    }
+    pub fn board_to_screen(&self, board: Position2d) -> Position2d {
+        let origin = self.board.position();
+        Position2d {
+            x: (board.x - origin.x) * self.scale + self.offset.x,
+            y: (board.y - origin.y) * self.scale + self.offset.y,
+        }
+    }
+    /// Makes further drawing on `context` use board coordinates.
+    pub fn apply(&self, context: &web_sys::CanvasRenderingContext2d) {
+        let origin = self.board.position();
+        let (ratio, scale) = (self.pixel_ratio, self.scale * self.pixel_ratio);
+        context
+            .set_transform(
+                scale,
+                0.0,
+                0.0,
+                scale,
+                (self.offset.x - origin.x * self.scale) * ratio,
+                (self.offset.y - origin.y * self.scale) * ratio,
+            )
+            .unwrap();
+    }
+    /// Paints everything but the play area of `board` with the letterbox style,
+    /// clears the play area and makes further drawing on `context` use board coordinates.
+    pub fn prepare(
+        &self,
+        context: &web_sys::CanvasRenderingContext2d,
+        board: &dyn Board,
+        letterbox_style: &str,
+    ) {
+        let ratio = self.pixel_ratio;
+        context
+            .set_transform(ratio, 0.0, 0.0, ratio, 0.0, 0.0)
+            .unwrap();
+        context.set_fill_style_str(letterbox_style);
+        context.fill_rect(0.0, 0.0, self.screen_width, self.screen_height);
+        self.apply(context);
+        let origin = self.board.position();
+        context.save();
+        context.begin_path();
+        board.trace(&mut CanvasRenderer::new(context));
+        context.clip();
+        context.clear_rect(origin.x, origin.y, self.board.width(), self.board.height());
+        context.restore();
+    }
+}
+
+/// Device pixels per CSS pixel on the current display.
+fn device_pixel_ratio() -> f64 {
+    let ratio = web_sys::window().unwrap().device_pixel_ratio();
+    if ratio.is_finite() && ratio > 0.0 {
+        ratio
+    } else {
+        1.0
+    }
+}
+
+/// The size of the canvas in CSS pixels, and the device pixels per CSS pixel it holds.
+pub fn screen_size(canvas: &web_sys::HtmlCanvasElement) -> (f64, f64, f64) {
+    let ratio = device_pixel_ratio();
+    let width = (f64::from(canvas.width()) / ratio).max(1.0);
+    let height = (f64::from(canvas.height()) / ratio).max(1.0);
+    (width, height, ratio)
+}
+
+/// Sizes the canvas to the width of its container and the rest of the window
+/// below its top edge, with a backing store dense enough for the display.
+/// Returns the new size in CSS pixels and the pixel ratio.
+pub fn fit_canvas(canvas: &web_sys::HtmlCanvasElement) -> (f64, f64, f64) {
+    let window = web_sys::window().unwrap();
+    let width = canvas
+        .parent_element()
+        .map(|c| c.client_width())
+        .filter(|&w| w > 0)
+        .unwrap_or_else(|| window.inner_width().unwrap().as_f64().unwrap() as i32);
+    let top = canvas.get_bounding_client_rect().top() + window.scroll_y().unwrap_or(0.0);
+    let height = (window.inner_height().unwrap().as_f64().unwrap() - top) as i32;
+    let (width, height) = (width.max(1) as u32, height.max(1) as u32);
+    canvas
+        .style()
+        .set_property("height", &format!("{}px", height))
+        .unwrap();
+    let ratio = device_pixel_ratio();
+    canvas.set_width((f64::from(width) * ratio).round() as u32);
+    canvas.set_height((f64::from(height) * ratio).round() as u32);
+    (width.into(), height.into(), ratio)
+}
+
+/// Refits the canvas once the pixel ratio changes from what it is now,
+/// e.g. when the window moves to another monitor or the page is zoomed.
+fn watch_pixel_ratio(canvas: &web_sys::HtmlCanvasElement) -> Result<(), JsValue> {
+    let query = format!("(resolution: {}dppx)", device_pixel_ratio());
+    let media = match web_sys::window().unwrap().match_media(&query)? {
+        Some(media) => media,
+        None => return Ok(()),
+    };
+    let target = canvas.clone();
+    let changed = Closure::once_into_js(move || {
+        refit(&target);
+        //The query was for the old ratio, so ask anew for the next change.
+        let _ = watch_pixel_ratio(&target);
+    });
+    let options = web_sys::AddEventListenerOptions::new();
+    options.set_once(true);
+    media.add_event_listener_with_callback_and_add_event_listener_options(
+        "change",
+        changed.unchecked_ref(),
+        &options,
+    )
+}
+
+fn refit(canvas: &web_sys::HtmlCanvasElement) {
+    let (width, height, ratio) = fit_canvas(canvas);
+    with_session(|session| session.set_screen_size(width, height, ratio));
+}
+
+/// Makes the canvas follow its container and refits the game on every `resize`
+/// and change of the pixel ratio.
+pub fn install(canvas: &web_sys::HtmlCanvasElement) -> Result<(), JsValue> {
+    let style = canvas.style();
+    style.set_property("display", "block")?;
+    style.set_property("width", "100%")?;
+    fit_canvas(canvas);
+    let target = canvas.clone();
+    let closure = Closure::wrap(Box::new(move || refit(&target)) as Box<dyn FnMut()>);
+    web_sys::window()
+        .unwrap()
+        .add_event_listener_with_callback("resize", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+    watch_pixel_ratio(canvas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn letterbox_wide_screen() {
+        let viewport = Viewport::fit(Rect::new(0.0, 0.0, 1280.0, 720.0), 1920.0, 720.0, 1.0);
+        assert_eq!(viewport.scale(), 1.0);
+        assert_eq!(viewport.offset(), Position2d { x: 320.0, y: 0.0 });
+    }
+    #[test]
+    fn letterbox_tall_screen() {
+        let viewport = Viewport::fit(Rect::new(0.0, 0.0, 1280.0, 720.0), 640.0, 1000.0, 1.0);
+        assert_eq!(viewport.scale(), 0.5);
+        assert_eq!(viewport.offset(), Position2d { x: 0.0, y: 320.0 });
+    }
+    #[test]
+    fn round_trip() {
+        let viewport = Viewport::fit(Rect::new(100.0, 50.0, 1280.0, 720.0), 640.0, 1000.0, 1.0);
+        let board = Position2d { x: 740.0, y: 410.0 };
+        let screen = viewport.board_to_screen(board);
+        assert_eq!(screen, Position2d { x: 320.0, y: 500.0 });
+        assert_eq!(viewport.screen_to_board(screen), board);
+    }
+    #[test]
+    fn pixel_ratio_keeps_css_pixels() {
+        let board = Rect::new(0.0, 0.0, 1280.0, 720.0);
+        let dense = Viewport::fit(board, 640.0, 1000.0, 2.0);
+        let plain = Viewport::fit(board, 640.0, 1000.0, 1.0);
+        assert_eq!(dense.scale(), plain.scale());
+        assert_eq!(dense.offset(), plain.offset());
+        assert_eq!(dense.pixel_ratio(), 2.0);
+    }
+}